@@ -1,11 +1,12 @@
-use codewandler_audio::{AudioPlayback, convert_pcm16_bytes_to_f32};
+use codewandler_audio::AudioPlayback;
 use crossbeam_channel::Sender;
+use openai_realtime::audio::pipeline::{AudioInput, AudioOutput, PcmFormat};
 use openai_realtime::{
-    AgentConfig, Model, RealtimeSession, ResponseCreateEvent, Voice, connect_realtime_agent,
+    AgentConfig, AudioReceiver, Budget, BudgetAction, Model, RealtimeSession, ResponseCreateEvent,
+    Voice, connect_realtime_agent,
 };
 use std::ops::Add;
 use std::sync::Arc;
-use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::task::JoinHandle;
 
 use clap::Parser;
@@ -28,20 +29,37 @@ struct Args {
 
     #[arg(long)]
     starter: Option<String>,
+
+    /// Maximum cost of each agent's session in USD
+    #[arg(long, default_value = "1.0")]
+    budget: f64,
 }
 
 fn pipe(
     playback: Sender<f32>,
-    mut rx: UnboundedReceiver<Vec<u8>>,
-    session: Arc<RealtimeSession>,
+    format: PcmFormat,
+    rx: AudioReceiver,
+    from: Arc<RealtimeSession>,
+    to: Arc<RealtimeSession>,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
-        while let Some(data) = rx.recv().await {
-            for s in convert_pcm16_bytes_to_f32(data.clone()) {
-                playback.send(s).unwrap()
+        let mut output = AudioOutput::new(from, rx, format);
+        let mut input = AudioInput::new(to, format);
+        while let Some(samples) = output.recv().await {
+            if samples
+                .iter()
+                .any(|s| playback.send(*s as f32 / 32768.0).is_err())
+            {
+                tracing::info!("playback stopped");
+                break;
             }
+            output.played(samples.len());
 
-            session.audio_append(data).unwrap();
+            // the session is closed, e.g. because its budget is exhausted
+            if let Err(err) = input.append(&samples) {
+                tracing::info!("stopped forwarding audio: {err}");
+                break;
+            }
         }
     })
 }
@@ -54,6 +72,10 @@ async fn main() -> anyhow::Result<()> {
     let model = Model::default();
 
     let args = Args::parse();
+    let budget = Budget {
+        max_cost: args.budget,
+        action: BudgetAction::Close,
+    };
 
     let (s1, s1_rx) = connect_realtime_agent(AgentConfig {
         instructions: args.prompt1.unwrap_or("You are Jen, your hobbies are programming and pizza. You try to find out something interesting about your conversation partner".to_string())
@@ -62,6 +84,7 @@ async fn main() -> anyhow::Result<()> {
         voice: Voice::Verse.into(),
         model: model.clone().into(),
         speed: args.speed.clone(),
+        budget: Some(budget),
         ..Default::default()
     }).await?;
 
@@ -72,6 +95,7 @@ async fn main() -> anyhow::Result<()> {
         voice: Voice::Sage.into(),
         model: model.clone().into(),
         speed: args.speed.clone(),
+        budget: Some(budget),
         ..Default::default()
     }).await?;
 
@@ -84,11 +108,15 @@ async fn main() -> anyhow::Result<()> {
         ..Default::default()
     })?;
 
+    let format = PcmFormat::mono(24_000);
     let pb = AudioPlayback::new(sr)?;
     let o1 = pb.new_output(sr);
     let o2 = pb.new_output(sr);
 
-    let (r1, r2) = tokio::join!(pipe(o1, s1_rx, s2.clone()), pipe(o2, s2_rx, s1.clone()));
+    let (r1, r2) = tokio::join!(
+        pipe(o1, format, s1_rx, s1.clone(), s2.clone()),
+        pipe(o2, format, s2_rx, s2.clone(), s1.clone())
+    );
     r1.expect("pipe#1 failed");
     r2.expect("pipe#2 failed");
 
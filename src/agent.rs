@@ -1,37 +1,105 @@
+use crate::api::item::{FunctionCallOutputItem, Item};
 use crate::api::model::Model;
+use crate::cost::{PriceTable, TokenPrices};
+use crate::tool::ToolRegistry;
 use crate::{
-    AudioFormat, Modality, SessionUpdateEvent, TurnDetection, Voice, WebsocketConfig, websocket,
+    ApiKeyRef, AudioFormat, AudioReceiver, Endpoint, Event, FunctionCall, InputAudioTranscription,
+    Modality, NoiseReduction, RealtimeError, ReconnectPolicy, ResponseCreateEvent, ServerEvent,
+    SessionUpdateEvent, ToolChoice, TurnDetection, Voice, WebsocketConfig, websocket,
 };
+use serde_json::json;
 use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{error, warn};
 
 #[derive(Debug, Clone, Default)]
 pub struct AgentConfig {
+    pub endpoint: Endpoint,
+    pub api_key_ref: ApiKeyRef,
     pub model: Option<Model>,
     pub voice: Option<Voice>,
     pub speed: Option<f32>,
     pub instructions: Option<String>,
+    /// Transcribe the user's audio, see [`Event::InputTranscriptDone`]
+    pub input_audio_transcription: Option<InputAudioTranscription>,
+    /// `None` keeps the server's default
+    pub input_audio_noise_reduction: Option<NoiseReduction>,
+    pub reconnect: ReconnectPolicy,
+    /// Interrupt the assistant when the user starts speaking, see
+    /// [`RealtimeSession::interrupt`](websocket::RealtimeSession::interrupt). Off by default, set
+    /// it to `true` to enable it. Server turn detection is set up not to interrupt on its own, so
+    /// without barge-in the assistant always finishes its response.
+    pub barge_in: bool,
+    /// Share of a rate limit below which [`Event::RateLimitLow`] is sent, `None` keeps the
+    /// default of [`WebsocketConfig`]
+    pub rate_limit_threshold: Option<f64>,
+    /// Tools offered to the model. Calls are dispatched to the registered handlers and their
+    /// outputs are sent back automatically.
+    pub tools: ToolRegistry,
+    /// Prices used to enforce the `budget`
+    pub prices: PriceTable,
+    pub budget: Option<Budget>,
+}
+
+/// Spending limit of an agent session, checked after every response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    /// Maximum cost in USD
+    pub max_cost: f64,
+    pub action: BudgetAction,
+}
+
+/// What happens once the [`Budget`] is exceeded
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BudgetAction {
+    /// Cancel every further response, the session stays open
+    #[default]
+    CancelResponses,
+    /// Close the session
+    Close,
 }
 
 pub async fn connect_realtime_agent(
     config: AgentConfig,
-) -> anyhow::Result<(Arc<websocket::RealtimeSession>, UnboundedReceiver<Vec<u8>>)> {
+) -> Result<(Arc<websocket::RealtimeSession>, AudioReceiver), RealtimeError> {
     let voice = config.voice.unwrap_or(Voice::Echo);
-    let model = config.model.unwrap_or(Model::default());
+    let model = config.model.unwrap_or_default();
+    let budget = match config.budget {
+        Some(budget) => match config.prices.get(&model) {
+            Some(prices) => Some((budget, *prices)),
+            None => {
+                return Err(RealtimeError::InvalidConfig(format!(
+                    "no prices for model {model}"
+                )));
+            }
+        },
+        None => None,
+    };
 
     // create a new realtime agent
-    let rt_config = WebsocketConfig {
+    let mut rt_config = WebsocketConfig {
         model,
+        api_key_ref: config.api_key_ref,
+        endpoint: config.endpoint,
+        reconnect: config.reconnect,
+        barge_in: config.barge_in,
         ..Default::default()
     };
-    if rt_config.api_key_ref.api_key().is_empty() {
-        Err(anyhow::anyhow!(
+    if let Some(threshold) = config.rate_limit_threshold {
+        rt_config.rate_limit_threshold = threshold;
+    }
+    if !matches!(rt_config.api_key_ref, ApiKeyRef::Provider(_))
+        && rt_config.api_key_ref.api_key().await?.is_empty()
+    {
+        return Err(RealtimeError::InvalidConfig(format!(
             "invalid api key ref: {}",
             rt_config.api_key_ref
-        ))?;
+        )));
     }
 
-    let (rt_client, rx_audio) = websocket::connect(rt_config).await.unwrap();
+    let (rt_client, rx_audio) = websocket::connect(rt_config).await?;
 
     let instructions = config.instructions.unwrap_or(
         r###"
@@ -47,19 +115,399 @@ You language is en-US.
         speed: config.speed,
         voice: voice.clone().into(),
         modalities: vec![Modality::Audio, Modality::Text].into(),
-        turn_detection: TurnDetection {
-            create_response: true,
-            interrupt_response: false,
+        turn_detection: TurnDetection::ServerVad {
+            threshold: 0.5,
             prefix_padding_ms: 300,
             silence_duration_ms: 1000,
-            td_type: "server_vad".into(),
-            threshold: 0.5,
+            create_response: Some(true),
+            interrupt_response: Some(false),
         }
         .into(),
         input_audio_format: Some(AudioFormat::PCM16),
         output_audio_format: Some(AudioFormat::PCM16),
+        input_audio_transcription: config.input_audio_transcription.map(Some),
+        input_audio_noise_reduction: config.input_audio_noise_reduction,
+        tools: (!config.tools.is_empty()).then(|| config.tools.tools()),
+        tool_choice: (!config.tools.is_empty()).then_some(ToolChoice::Auto),
         ..Default::default()
     })?;
 
+    if let Some((budget, prices)) = budget {
+        tokio::spawn(enforce_budget(
+            rt_client.clone(),
+            prices,
+            budget,
+            rt_client.subscribe(),
+        ));
+    }
+
+    if !config.tools.is_empty() {
+        let rx_calls = rt_client.function_calls().await?;
+        tokio::spawn(dispatch_tool_calls(
+            rt_client.clone(),
+            config.tools,
+            rx_calls,
+        ));
+    }
+
     Ok((rt_client, rx_audio))
 }
+
+/// Runs the function calls of each response concurrently, returns their outputs and
+/// requests a single follow-up response.
+async fn dispatch_tool_calls(
+    session: Arc<websocket::RealtimeSession>,
+    tools: ToolRegistry,
+    mut rx_calls: UnboundedReceiver<Vec<FunctionCall>>,
+) {
+    while let Some(calls) = rx_calls.recv().await {
+        let handles: Vec<_> = calls
+            .into_iter()
+            .map(|call| {
+                let tools = tools.clone();
+                let handle = tokio::spawn({
+                    let call = call.clone();
+                    async move { tools.dispatch(&call).await }
+                });
+                (call, handle)
+            })
+            .collect();
+
+        for (call, handle) in handles {
+            let output = match handle.await {
+                Ok(output) => output,
+                Err(e) => {
+                    // the model still expects an output for every call
+                    error!("tool handler of {} panicked: {}", call.name, e);
+                    json!({ "error": format!("tool {} failed", call.name) }).to_string()
+                }
+            };
+            let item = Item::FunctionCallOutput(FunctionCallOutputItem {
+                id: None,
+                object: None,
+                status: None,
+                call_id: call.call_id,
+                output,
+            });
+            if let Err(e) = session.conversation_item_create(item, None) {
+                error!("error sending output of {}: {}", call.name, e);
+            }
+        }
+
+        if let Err(e) = session.response_create(ResponseCreateEvent::default()) {
+            error!("error requesting response after tool calls: {}", e);
+        }
+    }
+}
+
+/// Adds up the usage of all responses and applies the budget's action once it is exceeded
+async fn enforce_budget(
+    session: Arc<websocket::RealtimeSession>,
+    prices: TokenPrices,
+    budget: Budget,
+    mut events: broadcast::Receiver<Event>,
+) {
+    let mut exceeded = false;
+    loop {
+        // the session counts the usage, so a missed `response.done` is caught up with the next
+        let check = match events.recv().await {
+            Ok(Event::Server(ServerEvent::ResponseDone(_))) => !exceeded,
+            Ok(Event::Server(ServerEvent::ResponseCreated(evt))) if exceeded => {
+                if let Err(e) = session.response_cancel(Some(evt.response.id)) {
+                    error!("error cancelling response over budget: {}", e);
+                }
+                false
+            }
+            Ok(Event::Disconnected) | Err(RecvError::Closed) => return,
+            Ok(_) => false,
+            Err(RecvError::Lagged(n)) => {
+                warn!("budget missed {} events", n);
+                !exceeded
+            }
+        };
+        if !check {
+            continue;
+        }
+        let cost = prices.cost(&session.usage());
+        if cost <= budget.max_cost {
+            continue;
+        }
+        exceeded = true;
+        warn!(
+            "budget exceeded: {:.4} USD of {:.4} USD",
+            cost, budget.max_cost
+        );
+        session.broadcast(Event::BudgetExceeded {
+            cost,
+            budget: budget.max_cost,
+        });
+        if budget.action == BudgetAction::Close {
+            session.close();
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{AgentConfig, Budget, BudgetAction, connect_realtime_agent};
+    use crate::api::client_event::ClientEvent;
+    use crate::api::item::Item;
+    use crate::api::model::Model;
+    use crate::api::server_event::ServerEvent;
+    use crate::config::ApiKeyRef;
+    use crate::error::RealtimeError;
+    use crate::event::Event;
+    use crate::testing::{MOCK_API_KEY, MockServer};
+    use crate::tool::ToolRegistry;
+    use crate::websocket::ConnectionState;
+    use base64::prelude::*;
+    use serde_json::{Value, json};
+
+    fn response(event: &str, id: &str, status: &str) -> String {
+        json!({
+            "type": event, "event_id": "event_1",
+            "response": {
+                "id": id, "object": "realtime.response", "status": status, "output": [],
+                "usage": {
+                    "total_tokens": 10000, "input_tokens": 0, "output_tokens": 10000,
+                    "input_token_details": { "text_tokens": 0, "audio_tokens": 0, "cached_tokens": 0 },
+                    "output_token_details": { "text_tokens": 0, "audio_tokens": 10000 }
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_budget() {
+        let mock = MockServer::start().await;
+        let config = |action| AgentConfig {
+            endpoint: mock.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            // 10k audio output tokens cost 0.80 USD
+            budget: Some(Budget {
+                max_cost: 1.0,
+                action,
+            }),
+            ..Default::default()
+        };
+
+        // prices are checked before connecting
+        let Err(err) = connect_realtime_agent(AgentConfig {
+            model: Some(Model("my-realtime".to_string())),
+            ..config(BudgetAction::Close)
+        })
+        .await
+        else {
+            panic!("expected the agent to be rejected")
+        };
+        assert!(matches!(err, RealtimeError::InvalidConfig(_)), "{err}");
+        assert!(mock.requests().is_empty());
+
+        let (session, _rx_audio) = connect_realtime_agent(config(BudgetAction::CancelResponses))
+            .await
+            .unwrap();
+        let mut events = session.subscribe();
+        mock.send_raw(response("response.done", "resp_1", "completed"));
+        mock.send_raw(response("response.done", "resp_2", "completed"));
+        let cost = loop {
+            if let Event::BudgetExceeded { cost, .. } = events.recv().await.unwrap() {
+                break cost;
+            }
+        };
+        assert!((cost - 1.6).abs() < 1e-9, "{cost}");
+        mock.send_raw(response("response.created", "resp_3", "in_progress"));
+        let (_, cancel) = mock
+            .wait_for(|evt| matches!(evt, ClientEvent::ResponseCancel { .. }))
+            .await
+            .unwrap();
+        assert!(matches!(
+            cancel,
+            ClientEvent::ResponseCancel { response_id: Some(id) } if id == "resp_3"
+        ));
+
+        let (session, _rx_audio) = connect_realtime_agent(config(BudgetAction::Close))
+            .await
+            .unwrap();
+        let mut state = session.connection_state();
+        mock.send_raw(response("response.done", "resp_1", "completed"));
+        mock.send_raw(response("response.done", "resp_2", "completed"));
+        state
+            .wait_for(|s| *s == ConnectionState::Disconnected)
+            .await
+            .unwrap();
+        assert!(session.response_create(Default::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tool_panic() {
+        let mock = MockServer::start().await;
+        let mut tools = ToolRegistry::default();
+        tools.register("echo", "Echoes", |args: Value| async move {
+            Ok::<_, String>(args)
+        });
+        tools.register("panic", "Panics", |_: Value| async move {
+            if true {
+                panic!("tool bug");
+            }
+            Ok::<_, String>(Value::Null)
+        });
+        let (session, _rx_audio) = connect_realtime_agent(AgentConfig {
+            endpoint: mock.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            tools,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        // the agent dispatches the calls
+        assert!(matches!(
+            session.function_calls().await,
+            Err(RealtimeError::InvalidState(_))
+        ));
+
+        let call = |call_id: &str, name: &str| {
+            json!({
+                "id": format!("item_{call_id}"), "type": "function_call", "status": "completed",
+                "call_id": call_id, "name": name, "arguments": "{}"
+            })
+        };
+        mock.send_raw(
+            json!({
+                "type": "response.done", "event_id": "event_1",
+                "response": {
+                    "id": "resp_1", "object": "realtime.response", "status": "completed",
+                    "output": [call("call_1", "panic"), call("call_2", "echo")]
+                }
+            })
+            .to_string(),
+        );
+        mock.wait_for(|evt| matches!(evt, ClientEvent::ResponseCreate { .. }))
+            .await
+            .unwrap();
+        let outputs: Vec<_> = mock
+            .received()
+            .into_iter()
+            .filter_map(|evt| match evt {
+                ClientEvent::ConversationItemCreate {
+                    item: Item::FunctionCallOutput(item),
+                    ..
+                } => Some((
+                    item.call_id,
+                    serde_json::from_str::<Value>(&item.output).unwrap(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            outputs,
+            [
+                (
+                    "call_1".to_string(),
+                    json!({ "error": "tool panic failed" })
+                ),
+                ("call_2".to_string(), json!({})),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_barge_in() {
+        let mock = MockServer::start().await;
+        let (session, mut rx_audio) = connect_realtime_agent(AgentConfig {
+            endpoint: mock.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let mut events = session.subscribe();
+        mock.send_raw(response("response.created", "resp_1", "in_progress"));
+        mock.send_raw(
+            json!({
+                "type": "response.content_part.added", "event_id": "event_2",
+                "response_id": "resp_1", "item_id": "item_1", "output_index": 0,
+                "content_index": 0, "part": { "type": "audio", "transcript": "" }
+            })
+            .to_string(),
+        );
+        mock.send_raw(
+            json!({
+                "type": "response.audio.delta", "event_id": "event_3",
+                "response_id": "resp_1", "item_id": "item_1", "output_index": 0,
+                "content_index": 0, "delta": BASE64_STANDARD.encode([1; 4800])
+            })
+            .to_string(),
+        );
+        assert_eq!(rx_audio.recv().await.unwrap().len(), 4800);
+        session.audio_played(960);
+
+        mock.send_raw(
+            json!({
+                "type": "input_audio_buffer.speech_started", "event_id": "event_4",
+                "audio_start_ms": 1000, "item_id": "item_2"
+            })
+            .to_string(),
+        );
+        // handled once the next event is broadcast
+        mock.send_raw(
+            json!({
+                "type": "input_audio_buffer.speech_stopped", "event_id": "event_5",
+                "audio_end_ms": 2000, "item_id": "item_2"
+            })
+            .to_string(),
+        );
+        loop {
+            match events.recv().await.unwrap() {
+                Event::InputAudioBufferSpeechStarted {
+                    item_id,
+                    audio_start_ms,
+                } => {
+                    assert_eq!(item_id, "item_2");
+                    assert_eq!(audio_start_ms, 1000);
+                }
+                Event::Server(ServerEvent::InputAudioBufferSpeechStopped(_)) => break,
+                _ => {}
+            }
+        }
+        session.input_audio_buffer_clear().unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::InputAudioBufferClear))
+            .await
+            .unwrap();
+        assert!(!mock.received().iter().any(|evt| matches!(
+            evt,
+            ClientEvent::ResponseCancel { .. } | ClientEvent::ConversationItemTruncate { .. }
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_threshold() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect_realtime_agent(AgentConfig {
+            endpoint: mock.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            rate_limit_threshold: Some(0.5),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let mut events = session.subscribe();
+        // 40% left is low for the agent, not for the default threshold of 10%
+        mock.send_raw(
+            json!({
+                "type": "rate_limits.updated", "event_id": "event_1",
+                "rate_limits": [
+                    { "name": "tokens", "limit": 50000, "remaining": 20000, "reset_seconds": 1.2 }
+                ]
+            })
+            .to_string(),
+        );
+        let limit = loop {
+            if let Event::RateLimitLow(limit) = events.recv().await.unwrap() {
+                break limit;
+            }
+        };
+        assert_eq!(limit.remaining, 20_000);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::api::item::Item;
+use crate::api::response::ResponseCreateEvent;
+use crate::api::session::{SessionUpdateEvent, TranscriptionSessionUpdateEvent};
+use serde::{Deserialize, Serialize};
+
+/// Events sent by the client over the websocket
+/// See: https://platform.openai.com/docs/api-reference/realtime-client-events
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum ClientEvent {
+    #[serde(rename = "session.update")]
+    SessionUpdate { session: SessionUpdateEvent },
+
+    #[serde(rename = "transcription_session.update")]
+    TranscriptionSessionUpdate {
+        session: TranscriptionSessionUpdateEvent,
+    },
+
+    /// `audio` holds base64-encoded audio bytes
+    #[serde(rename = "input_audio_buffer.append")]
+    InputAudioBufferAppend { audio: String },
+
+    #[serde(rename = "input_audio_buffer.commit")]
+    InputAudioBufferCommit,
+
+    #[serde(rename = "input_audio_buffer.clear")]
+    InputAudioBufferClear,
+
+    #[serde(rename = "conversation.item.create")]
+    ConversationItemCreate {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        previous_item_id: Option<String>,
+        item: Item,
+    },
+
+    #[serde(rename = "conversation.item.retrieve")]
+    ConversationItemRetrieve { item_id: String },
+
+    #[serde(rename = "conversation.item.truncate")]
+    ConversationItemTruncate {
+        item_id: String,
+        content_index: u32,
+        audio_end_ms: u32,
+    },
+
+    #[serde(rename = "conversation.item.delete")]
+    ConversationItemDelete { item_id: String },
+
+    #[serde(rename = "response.create")]
+    ResponseCreate { response: ResponseCreateEvent },
+
+    #[serde(rename = "response.cancel")]
+    ResponseCancel {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        response_id: Option<String>,
+    },
+
+    /// WebRTC only: cuts off the current audio response
+    #[serde(rename = "output_audio_buffer.clear")]
+    OutputAudioBufferClear,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::client_event::ClientEvent;
+    use crate::event::EventMessage;
+    use serde_json::json;
+
+    #[test]
+    fn test_encode_client_events() {
+        let msg = EventMessage::new(ClientEvent::ConversationItemTruncate {
+            item_id: "msg_002".into(),
+            content_index: 0,
+            audio_end_ms: 1500,
+        });
+        let value = serde_json::to_value(&msg).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "event_id": msg.event_id,
+                "type": "conversation.item.truncate",
+                "item_id": "msg_002",
+                "content_index": 0,
+                "audio_end_ms": 1500
+            })
+        );
+
+        let value = serde_json::to_value(ClientEvent::InputAudioBufferCommit).unwrap();
+        assert_eq!(value, json!({ "type": "input_audio_buffer.commit" }));
+
+        let value =
+            serde_json::to_value(ClientEvent::ResponseCancel { response_id: None }).unwrap();
+        assert_eq!(value, json!({ "type": "response.cancel" }));
+    }
+}
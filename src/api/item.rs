@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Assistant,
+    System,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Completed,
+    Incomplete,
+    InProgress,
+}
+
+/// A content part of a message item
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events/conversation/item/created
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    InputText {
+        text: String,
+    },
+    InputAudio {
+        /// Base64-encoded audio bytes
+        #[serde(skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+    ItemReference {
+        id: String,
+    },
+    Text {
+        text: String,
+    },
+    Audio {
+        /// Base64-encoded audio bytes
+        #[serde(skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ItemStatus>,
+
+    pub role: Role,
+
+    pub content: Vec<ContentPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FunctionCallItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ItemStatus>,
+
+    pub call_id: String,
+
+    pub name: String,
+
+    /// JSON-encoded arguments of the function call
+    pub arguments: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FunctionCallOutputItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ItemStatus>,
+
+    pub call_id: String,
+
+    pub output: String,
+}
+
+/// An item of the conversation
+/// See: https://platform.openai.com/docs/api-reference/realtime-client-events/conversation/item/create
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Item {
+    Message(MessageItem),
+    FunctionCall(FunctionCallItem),
+    FunctionCallOutput(FunctionCallOutputItem),
+    /// An item type this client does not know yet, kept as received
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+impl Item {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Item::Message(item) => item.id.as_deref(),
+            Item::FunctionCall(item) => item.id.as_deref(),
+            Item::FunctionCallOutput(item) => item.id.as_deref(),
+            Item::Unknown(item) => item.get("id").and_then(serde_json::Value::as_str),
+        }
+    }
+
+    pub(crate) fn set_id(&mut self, id: String) {
+        match self {
+            Item::Message(item) => item.id = Some(id),
+            Item::FunctionCall(item) => item.id = Some(id),
+            Item::FunctionCallOutput(item) => item.id = Some(id),
+            Item::Unknown(item) => {
+                if let Some(item) = item.as_object_mut() {
+                    item.insert("id".to_string(), id.into());
+                }
+            }
+        }
+    }
+}
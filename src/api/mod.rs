@@ -1,4 +1,7 @@
+pub mod client_event;
+pub mod item;
 pub mod model;
 pub mod response;
+pub mod server_event;
 pub mod session;
 pub mod voice;
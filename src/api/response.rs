@@ -1,8 +1,10 @@
-use crate::api::session::Modality;
+use crate::api::item::Item;
+use crate::api::session::{Modality, Tool, ToolChoice};
 use crate::api::voice::Voice;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
 
-#[derive(Debug, Serialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct ResponseCreateEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modalities: Option<Vec<Modality>>,
@@ -10,5 +12,208 @@ pub struct ResponseCreateEvent {
     pub instructions: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub voice: Option<Voice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
     // TODO: other inference options
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    InProgress,
+    Completed,
+    Cancelled,
+    Failed,
+    Incomplete,
+}
+
+/// The response resource
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events/response/created
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Response {
+    pub id: String,
+
+    pub object: String,
+
+    pub status: ResponseStatus,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_details: Option<ResponseStatusDetails>,
+
+    #[serde(default)]
+    pub output: Vec<Item>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Why a response did not complete
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseStatusDetails {
+    Cancelled {
+        #[serde(default)]
+        reason: Option<StatusReason>,
+    },
+    Incomplete {
+        #[serde(default)]
+        reason: Option<StatusReason>,
+    },
+    Failed {
+        #[serde(default)]
+        error: Option<ResponseError>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusReason {
+    /// Cancelled because the user started speaking
+    TurnDetected,
+    /// Cancelled with `response.cancel`
+    ClientCancelled,
+    /// Incomplete because the response reached `max_response_output_tokens`
+    MaxOutputTokens,
+    /// Incomplete because the content filter stopped it
+    ContentFilter,
+    #[serde(untagged)]
+    Other(String),
+}
+
+/// The error of a failed response
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+/// Tokens used by a response, or the sum over several responses
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events/response/done
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct Usage {
+    pub total_tokens: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub input_token_details: InputTokenDetails,
+    pub output_token_details: OutputTokenDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct InputTokenDetails {
+    pub text_tokens: u64,
+    pub audio_tokens: u64,
+    /// Input tokens served from the cache, included in `text_tokens` and `audio_tokens`
+    pub cached_tokens: u64,
+    pub cached_tokens_details: CachedTokenDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct CachedTokenDetails {
+    pub text_tokens: u64,
+    pub audio_tokens: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct OutputTokenDetails {
+    pub text_tokens: u64,
+    pub audio_tokens: u64,
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.total_tokens += other.total_tokens;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        let input = &mut self.input_token_details;
+        input.text_tokens += other.input_token_details.text_tokens;
+        input.audio_tokens += other.input_token_details.audio_tokens;
+        input.cached_tokens += other.input_token_details.cached_tokens;
+        input.cached_tokens_details.text_tokens +=
+            other.input_token_details.cached_tokens_details.text_tokens;
+        input.cached_tokens_details.audio_tokens +=
+            other.input_token_details.cached_tokens_details.audio_tokens;
+        self.output_token_details.text_tokens += other.output_token_details.text_tokens;
+        self.output_token_details.audio_tokens += other.output_token_details.audio_tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::item::Item;
+    use crate::api::response::{
+        Response, ResponseStatus, ResponseStatusDetails, StatusReason, Usage,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_response_done() {
+        let response: Response = serde_json::from_value(json!({
+            "id": "resp_1",
+            "object": "realtime.response",
+            "status": "incomplete",
+            "status_details": { "type": "incomplete", "reason": "max_output_tokens" },
+            "output": [{
+                "id": "msg_1", "object": "realtime.item", "type": "message", "role": "assistant",
+                "content": [{ "type": "audio", "transcript": "Hi" }]
+            }],
+            "usage": {
+                "total_tokens": 275,
+                "input_tokens": 127,
+                "output_tokens": 148,
+                "input_token_details": {
+                    "cached_tokens": 64, "text_tokens": 119, "audio_tokens": 8,
+                    "cached_tokens_details": { "text_tokens": 64, "audio_tokens": 0 }
+                },
+                "output_token_details": { "text_tokens": 36, "audio_tokens": 112 }
+            }
+        }))
+        .unwrap();
+        assert_eq!(response.status, ResponseStatus::Incomplete);
+        assert_eq!(
+            response.status_details,
+            Some(ResponseStatusDetails::Incomplete {
+                reason: Some(StatusReason::MaxOutputTokens)
+            })
+        );
+        assert!(matches!(response.output[0], Item::Message(_)));
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.input_token_details.cached_tokens, 64);
+        assert_eq!(usage.output_token_details.audio_tokens, 112);
+
+        let mut total = Usage::default();
+        total += usage;
+        total += usage;
+        assert_eq!(total.total_tokens, 550);
+        assert_eq!(
+            total.input_token_details.cached_tokens_details.text_tokens,
+            128
+        );
+
+        let failed: ResponseStatusDetails = serde_json::from_value(json!({
+            "type": "failed", "error": { "type": "server_error", "code": null }
+        }))
+        .unwrap();
+        assert!(matches!(
+            failed,
+            ResponseStatusDetails::Failed { error: Some(e) } if e.error_type == "server_error"
+        ));
+        let cancelled: ResponseStatusDetails =
+            serde_json::from_value(json!({ "type": "cancelled", "reason": "something_new" }))
+                .unwrap();
+        assert_eq!(
+            cancelled,
+            ResponseStatusDetails::Cancelled {
+                reason: Some(StatusReason::Other("something_new".to_string()))
+            }
+        );
+    }
+}
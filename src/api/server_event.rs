@@ -0,0 +1,502 @@
+use crate::api::item::{ContentPart, Item};
+use crate::api::response::Response;
+use crate::api::session::{Session, TranscriptionSessionResource};
+use serde::{Deserialize, Serialize};
+
+/// Details of an error reported by the server
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events/error
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ErrorDetails {
+    #[serde(rename = "type")]
+    pub error_type: String,
+
+    #[serde(default)]
+    pub code: Option<String>,
+
+    pub message: String,
+
+    #[serde(default)]
+    pub param: Option<String>,
+
+    /// The `event_id` of the client event that caused the error, if applicable.
+    #[serde(default)]
+    pub event_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ErrorEvent {
+    pub event_id: String,
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionCreatedEvent {
+    pub event_id: String,
+    pub session: Session,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionUpdatedEvent {
+    pub event_id: String,
+    pub session: Session,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptionSessionEvent {
+    pub event_id: String,
+    pub session: TranscriptionSessionResource,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationResource {
+    pub id: String,
+    pub object: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationCreatedEvent {
+    pub event_id: String,
+    pub conversation: ConversationResource,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationItemCreatedEvent {
+    pub event_id: String,
+    #[serde(default)]
+    pub previous_item_id: Option<String>,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationItemRetrievedEvent {
+    pub event_id: String,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogProb {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioTranscriptionDeltaEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub delta: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Vec<LogProb>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioTranscriptionCompletedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub transcript: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Vec<LogProb>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioTranscriptionFailedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub error: ErrorDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationItemTruncatedEvent {
+    pub event_id: String,
+    pub item_id: String,
+    pub content_index: u32,
+    pub audio_end_ms: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationItemDeletedEvent {
+    pub event_id: String,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioBufferCommittedEvent {
+    pub event_id: String,
+    #[serde(default)]
+    pub previous_item_id: Option<String>,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioBufferClearedEvent {
+    pub event_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioBufferSpeechStartedEvent {
+    pub event_id: String,
+    pub audio_start_ms: u32,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InputAudioBufferSpeechStoppedEvent {
+    pub event_id: String,
+    pub audio_end_ms: u32,
+    pub item_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutputAudioBufferEvent {
+    pub event_id: String,
+    pub response_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseEvent {
+    pub event_id: String,
+    pub response: Response,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseOutputItemEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub output_index: u32,
+    pub item: Item,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseContentPartEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub part: ContentPart,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseTextDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseAudioTranscriptDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+    pub transcript: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseAudioDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub content_index: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseFunctionCallArgumentsDeltaEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub call_id: String,
+    pub delta: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResponseFunctionCallArgumentsDoneEvent {
+    pub event_id: String,
+    pub response_id: String,
+    pub item_id: String,
+    pub output_index: u32,
+    pub call_id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub arguments: String,
+}
+
+/// A rate limit as reported with `rate_limits.updated`
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events/rate_limits
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateLimit {
+    pub name: RateLimitName,
+    pub limit: u64,
+    pub remaining: u64,
+    /// Seconds until the limit is reset, at the time of the event
+    pub reset_seconds: f64,
+}
+
+impl RateLimit {
+    /// Remaining share of the limit, between 0 and 1
+    pub fn remaining_ratio(&self) -> f64 {
+        if self.limit == 0 {
+            return 0.0;
+        }
+        self.remaining as f64 / self.limit as f64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitName {
+    Requests,
+    Tokens,
+    #[serde(untagged)]
+    Other(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimitsUpdatedEvent {
+    pub event_id: String,
+    pub rate_limits: Vec<RateLimit>,
+}
+
+/// Events sent by the server over the websocket
+/// See: https://platform.openai.com/docs/api-reference/realtime-server-events
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
+pub enum ServerEvent {
+    #[serde(rename = "error")]
+    Error(ErrorEvent),
+
+    #[serde(rename = "session.created")]
+    SessionCreated(SessionCreatedEvent),
+
+    #[serde(rename = "session.updated")]
+    SessionUpdated(SessionUpdatedEvent),
+
+    #[serde(rename = "transcription_session.created")]
+    TranscriptionSessionCreated(TranscriptionSessionEvent),
+
+    #[serde(rename = "transcription_session.updated")]
+    TranscriptionSessionUpdated(TranscriptionSessionEvent),
+
+    #[serde(rename = "conversation.created")]
+    ConversationCreated(ConversationCreatedEvent),
+
+    #[serde(rename = "conversation.item.created")]
+    ConversationItemCreated(ConversationItemCreatedEvent),
+
+    #[serde(rename = "conversation.item.retrieved")]
+    ConversationItemRetrieved(ConversationItemRetrievedEvent),
+
+    #[serde(rename = "conversation.item.input_audio_transcription.delta")]
+    InputAudioTranscriptionDelta(InputAudioTranscriptionDeltaEvent),
+
+    #[serde(rename = "conversation.item.input_audio_transcription.completed")]
+    InputAudioTranscriptionCompleted(InputAudioTranscriptionCompletedEvent),
+
+    #[serde(rename = "conversation.item.input_audio_transcription.failed")]
+    InputAudioTranscriptionFailed(InputAudioTranscriptionFailedEvent),
+
+    #[serde(rename = "conversation.item.truncated")]
+    ConversationItemTruncated(ConversationItemTruncatedEvent),
+
+    #[serde(rename = "conversation.item.deleted")]
+    ConversationItemDeleted(ConversationItemDeletedEvent),
+
+    #[serde(rename = "input_audio_buffer.committed")]
+    InputAudioBufferCommitted(InputAudioBufferCommittedEvent),
+
+    #[serde(rename = "input_audio_buffer.cleared")]
+    InputAudioBufferCleared(InputAudioBufferClearedEvent),
+
+    #[serde(rename = "input_audio_buffer.speech_started")]
+    InputAudioBufferSpeechStarted(InputAudioBufferSpeechStartedEvent),
+
+    #[serde(rename = "input_audio_buffer.speech_stopped")]
+    InputAudioBufferSpeechStopped(InputAudioBufferSpeechStoppedEvent),
+
+    #[serde(rename = "output_audio_buffer.started")]
+    OutputAudioBufferStarted(OutputAudioBufferEvent),
+
+    #[serde(rename = "output_audio_buffer.stopped")]
+    OutputAudioBufferStopped(OutputAudioBufferEvent),
+
+    #[serde(rename = "output_audio_buffer.cleared")]
+    OutputAudioBufferCleared(OutputAudioBufferEvent),
+
+    #[serde(rename = "response.created")]
+    ResponseCreated(ResponseEvent),
+
+    #[serde(rename = "response.done")]
+    ResponseDone(ResponseEvent),
+
+    #[serde(rename = "response.output_item.added")]
+    ResponseOutputItemAdded(ResponseOutputItemEvent),
+
+    #[serde(rename = "response.output_item.done")]
+    ResponseOutputItemDone(ResponseOutputItemEvent),
+
+    #[serde(rename = "response.content_part.added")]
+    ResponseContentPartAdded(ResponseContentPartEvent),
+
+    #[serde(rename = "response.content_part.done")]
+    ResponseContentPartDone(ResponseContentPartEvent),
+
+    #[serde(rename = "response.text.delta")]
+    ResponseTextDelta(ResponseDeltaEvent),
+
+    #[serde(rename = "response.text.done")]
+    ResponseTextDone(ResponseTextDoneEvent),
+
+    #[serde(rename = "response.audio_transcript.delta")]
+    ResponseAudioTranscriptDelta(ResponseDeltaEvent),
+
+    #[serde(rename = "response.audio_transcript.done")]
+    ResponseAudioTranscriptDone(ResponseAudioTranscriptDoneEvent),
+
+    /// `delta` holds base64-encoded audio bytes
+    #[serde(rename = "response.audio.delta")]
+    ResponseAudioDelta(ResponseDeltaEvent),
+
+    #[serde(rename = "response.audio.done")]
+    ResponseAudioDone(ResponseAudioDoneEvent),
+
+    #[serde(rename = "response.function_call_arguments.delta")]
+    ResponseFunctionCallArgumentsDelta(ResponseFunctionCallArgumentsDeltaEvent),
+
+    #[serde(rename = "response.function_call_arguments.done")]
+    ResponseFunctionCallArgumentsDone(ResponseFunctionCallArgumentsDoneEvent),
+
+    #[serde(rename = "rate_limits.updated")]
+    RateLimitsUpdated(RateLimitsUpdatedEvent),
+
+    /// An event type this client does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::item::Item;
+    use crate::api::server_event::{RateLimitName, ServerEvent};
+
+    #[test]
+    fn test_decode_server_events() {
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{
+                "event_id": "event_567",
+                "type": "error",
+                "error": {
+                    "type": "invalid_request_error",
+                    "code": "invalid_event",
+                    "message": "The 'type' field is missing.",
+                    "param": null,
+                    "event_id": "event_567"
+                }
+            }"#,
+        )
+        .unwrap();
+        let ServerEvent::Error(evt) = evt else {
+            panic!("expected error event")
+        };
+        assert_eq!(evt.error.code.as_deref(), Some("invalid_event"));
+
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{
+                "event_id": "event_1920",
+                "type": "conversation.item.created",
+                "previous_item_id": "msg_002",
+                "item": {
+                    "id": "msg_003",
+                    "object": "realtime.item",
+                    "type": "message",
+                    "status": "completed",
+                    "role": "user",
+                    "content": [{ "type": "input_audio", "transcript": "hello how are you" }]
+                }
+            }"#,
+        )
+        .unwrap();
+        let ServerEvent::ConversationItemCreated(evt) = evt else {
+            panic!("expected conversation.item.created")
+        };
+        assert!(matches!(evt.item, Item::Message(_)));
+        assert_eq!(evt.item.id(), Some("msg_003"));
+
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{
+                "event_id": "event_5758",
+                "type": "rate_limits.updated",
+                "rate_limits": [
+                    { "name": "requests", "limit": 1000, "remaining": 999, "reset_seconds": 60 },
+                    { "name": "tokens", "limit": 50000, "remaining": 49950, "reset_seconds": 60 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let ServerEvent::RateLimitsUpdated(evt) = evt else {
+            panic!("expected rate_limits.updated")
+        };
+        assert_eq!(evt.rate_limits.len(), 2);
+        assert_eq!(evt.rate_limits[1].name, RateLimitName::Tokens);
+        assert_eq!(evt.rate_limits[1].remaining_ratio(), 0.999);
+
+        // new event types and fields don't break decoding
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{ "event_id": "event_1", "type": "output_audio_buffer.unknown", "foo": 1 }"#,
+        )
+        .unwrap();
+        assert!(matches!(evt, ServerEvent::Unknown));
+
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{
+                "event_id": "event_3",
+                "type": "conversation.item.created",
+                "previous_item_id": null,
+                "item": { "id": "mcp_001", "type": "mcp_call", "name": "search", "arguments": "{}" }
+            }"#,
+        )
+        .unwrap();
+        let ServerEvent::ConversationItemCreated(evt) = evt else {
+            panic!("expected conversation.item.created")
+        };
+        assert!(matches!(evt.item, Item::Unknown(_)));
+        assert_eq!(evt.item.id(), Some("mcp_001"));
+        assert_eq!(serde_json::to_value(&evt.item).unwrap()["type"], "mcp_call");
+
+        let evt: ServerEvent = serde_json::from_str(
+            r#"{
+                "event_id": "event_2",
+                "type": "session.updated",
+                "session": {
+                    "id": "sess_001", "object": "realtime.session", "expires_at": 0,
+                    "turn_detection": null, "input_audio_format": "pcm16", "include": null,
+                    "model": "gpt-realtime", "modalities": ["audio"], "instructions": "",
+                    "voice": "marin", "output_audio_format": "pcm16", "tool_choice": "auto",
+                    "temperature": 0.8, "max_response_output_tokens": "inf", "speed": 1.0,
+                    "tracing": null, "tools": [], "max_output_audio_seconds": 60
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(evt, ServerEvent::SessionUpdated(_)));
+    }
+}
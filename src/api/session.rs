@@ -1,6 +1,6 @@
 use crate::api::model::Model;
 use crate::api::voice::Voice;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -9,23 +9,288 @@ pub struct CreateSessionRequest {
     pub voice: Voice,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct TurnDetection {
-    #[serde(rename = "type")]
-    pub td_type: String,
+/// How the server detects the end of the user's turn
+/// See: https://platform.openai.com/docs/guides/realtime-vad
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "Option<TurnDetectionRepr>", into = "Option<TurnDetectionRepr>")]
+pub enum TurnDetection {
+    /// Detects turns by periods of silence
+    ServerVad {
+        /// Activation threshold between 0.0 and 1.0, higher requires louder audio
+        threshold: f32,
+        /// Audio included before the detected speech
+        prefix_padding_ms: i64,
+        /// Silence that ends the turn
+        silence_duration_ms: i64,
+        /// Create a response at the end of the turn, `None` keeps the server's default, true
+        create_response: Option<bool>,
+        /// Interrupt the response when the user starts speaking, `None` keeps the server's
+        /// default, true
+        interrupt_response: Option<bool>,
+    },
+    /// Detects turns by what the user said, e.g. waits longer after "ummm..."
+    SemanticVad {
+        eagerness: Eagerness,
+        create_response: Option<bool>,
+        interrupt_response: Option<bool>,
+    },
+    /// Turns are committed manually with `input_audio_buffer.commit`. Sent as `null`.
+    None,
+    /// A turn detection type not known to this crate, sent as is
+    Other(Value),
+}
 
-    pub threshold: f32,
+impl TurnDetection {
+    /// Server VAD with the server's default settings
+    pub fn server_vad() -> Self {
+        TurnDetection::ServerVad {
+            threshold: 0.5,
+            prefix_padding_ms: 300,
+            silence_duration_ms: 500,
+            create_response: None,
+            interrupt_response: None,
+        }
+    }
+
+    /// Semantic VAD with the server's default settings
+    pub fn semantic_vad() -> Self {
+        TurnDetection::SemanticVad {
+            eagerness: Eagerness::Auto,
+            create_response: None,
+            interrupt_response: None,
+        }
+    }
+
+    /// Drops `create_response` and `interrupt_response`, which transcription sessions don't
+    /// accept
+    pub(crate) fn for_transcription(self) -> Self {
+        match self {
+            TurnDetection::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                ..
+            } => TurnDetection::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                create_response: None,
+                interrupt_response: None,
+            },
+            TurnDetection::SemanticVad { eagerness, .. } => TurnDetection::SemanticVad {
+                eagerness,
+                create_response: None,
+                interrupt_response: None,
+            },
+            other => other,
+        }
+    }
+}
 
-    pub prefix_padding_ms: i64,
-    pub silence_duration_ms: i64,
-    pub create_response: bool,
-    pub interrupt_response: bool,
+/// How quickly semantic VAD ends the user's turn, `auto` is `medium`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Eagerness {
+    Low,
+    Medium,
+    High,
+    #[default]
+    Auto,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TurnDetectionRepr {
+    ServerVad {
+        threshold: f32,
+        prefix_padding_ms: i64,
+        silence_duration_ms: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        create_response: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interrupt_response: Option<bool>,
+    },
+    SemanticVad {
+        #[serde(default)]
+        eagerness: Eagerness,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        create_response: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interrupt_response: Option<bool>,
+    },
+    #[serde(untagged)]
+    Other(Value),
+}
+
+impl From<Option<TurnDetectionRepr>> for TurnDetection {
+    fn from(repr: Option<TurnDetectionRepr>) -> Self {
+        match repr {
+            Some(TurnDetectionRepr::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                create_response,
+                interrupt_response,
+            }) => TurnDetection::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                create_response,
+                interrupt_response,
+            },
+            Some(TurnDetectionRepr::SemanticVad {
+                eagerness,
+                create_response,
+                interrupt_response,
+            }) => TurnDetection::SemanticVad {
+                eagerness,
+                create_response,
+                interrupt_response,
+            },
+            Some(TurnDetectionRepr::Other(value)) => TurnDetection::Other(value),
+            None => TurnDetection::None,
+        }
+    }
+}
+
+impl From<TurnDetection> for Option<TurnDetectionRepr> {
+    fn from(turn_detection: TurnDetection) -> Self {
+        match turn_detection {
+            TurnDetection::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                create_response,
+                interrupt_response,
+            } => Some(TurnDetectionRepr::ServerVad {
+                threshold,
+                prefix_padding_ms,
+                silence_duration_ms,
+                create_response,
+                interrupt_response,
+            }),
+            TurnDetection::SemanticVad {
+                eagerness,
+                create_response,
+                interrupt_response,
+            } => Some(TurnDetectionRepr::SemanticVad {
+                eagerness,
+                create_response,
+                interrupt_response,
+            }),
+            TurnDetection::None => None,
+            TurnDetection::Other(value) => Some(TurnDetectionRepr::Other(value)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum AudioFormat {
+    /// 16-bit little-endian mono PCM at 24 kHz
+    #[default]
     PCM16,
+    /// G.711 μ-law at 8 kHz
+    #[serde(rename = "g711_ulaw")]
+    G711Ulaw,
+    /// G.711 A-law at 8 kHz
+    #[serde(rename = "g711_alaw")]
+    G711Alaw,
+}
+
+impl AudioFormat {
+    /// Samples per second
+    pub fn sample_rate(&self) -> u32 {
+        match self {
+            AudioFormat::PCM16 => 24_000,
+            AudioFormat::G711Ulaw | AudioFormat::G711Alaw => 8_000,
+        }
+    }
+
+    /// Size of one mono sample in bytes
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            AudioFormat::PCM16 => 2,
+            AudioFormat::G711Ulaw | AudioFormat::G711Alaw => 1,
+        }
+    }
+
+    /// Duration of `samples` in milliseconds
+    pub fn samples_to_ms(&self, samples: u64) -> u32 {
+        (samples * 1000 / self.sample_rate() as u64) as u32
+    }
+
+    /// Duration of `bytes` of audio in milliseconds
+    pub fn bytes_to_ms(&self, bytes: usize) -> u32 {
+        self.samples_to_ms((bytes / self.bytes_per_sample()) as u64)
+    }
+}
+
+/// Noise reduction applied to the input audio before VAD and the model see it
+/// See: https://platform.openai.com/docs/api-reference/realtime-client-events/session/update#realtime-client-events/session/update-session-input_audio_noise_reduction
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(
+    from = "Option<NoiseReductionRepr>",
+    into = "Option<NoiseReductionRepr>"
+)]
+pub enum NoiseReduction {
+    /// For close-talking microphones such as headsets
+    NearField,
+    /// For far-field microphones such as laptop or conference room microphones
+    FarField,
+    /// Sent as `null`
+    #[default]
+    Disabled,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NoiseReductionRepr {
+    NearField,
+    FarField,
+}
+
+impl From<Option<NoiseReductionRepr>> for NoiseReduction {
+    fn from(repr: Option<NoiseReductionRepr>) -> Self {
+        match repr {
+            Some(NoiseReductionRepr::NearField) => NoiseReduction::NearField,
+            Some(NoiseReductionRepr::FarField) => NoiseReduction::FarField,
+            None => NoiseReduction::Disabled,
+        }
+    }
+}
+
+impl From<NoiseReduction> for Option<NoiseReductionRepr> {
+    fn from(noise_reduction: NoiseReduction) -> Self {
+        match noise_reduction {
+            NoiseReduction::NearField => Some(NoiseReductionRepr::NearField),
+            NoiseReduction::FarField => Some(NoiseReductionRepr::FarField),
+            NoiseReduction::Disabled => None,
+        }
+    }
+}
+
+/// Reads a present `null` as `Some`, for fields where `null` is a value rather than "unset"
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Writes turn detection without the response flags, see [`TurnDetection::for_transcription`]
+fn serialize_transcription_turn_detection<S>(
+    turn_detection: &Option<TurnDetection>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    turn_detection
+        .clone()
+        .map(TurnDetection::for_transcription)
+        .serialize(serializer)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,11 +300,62 @@ pub enum Modality {
     Audio,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "lowercase")]
+/// How the model chooses tools
+/// See: https://platform.openai.com/docs/api-reference/realtime-client-events/session/update#realtime-client-events/session/update-session-tool_choice
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "ToolChoiceRepr", into = "ToolChoiceRepr")]
 pub enum ToolChoice {
     Auto,
-    // TODO:
+    None,
+    Required,
+    Function { name: String },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ToolChoiceRepr {
+    Mode(ToolChoiceMode),
+    Function(FunctionToolChoice),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ToolChoiceMode {
+    Auto,
+    None,
+    Required,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum FunctionToolChoice {
+    Function { name: String },
+}
+
+impl From<ToolChoiceRepr> for ToolChoice {
+    fn from(repr: ToolChoiceRepr) -> Self {
+        match repr {
+            ToolChoiceRepr::Mode(ToolChoiceMode::Auto) => ToolChoice::Auto,
+            ToolChoiceRepr::Mode(ToolChoiceMode::None) => ToolChoice::None,
+            ToolChoiceRepr::Mode(ToolChoiceMode::Required) => ToolChoice::Required,
+            ToolChoiceRepr::Function(FunctionToolChoice::Function { name }) => {
+                ToolChoice::Function { name }
+            }
+        }
+    }
+}
+
+impl From<ToolChoice> for ToolChoiceRepr {
+    fn from(choice: ToolChoice) -> Self {
+        match choice {
+            ToolChoice::Auto => ToolChoiceRepr::Mode(ToolChoiceMode::Auto),
+            ToolChoice::None => ToolChoiceRepr::Mode(ToolChoiceMode::None),
+            ToolChoice::Required => ToolChoiceRepr::Mode(ToolChoiceMode::Required),
+            ToolChoice::Function { name } => {
+                ToolChoiceRepr::Function(FunctionToolChoice::Function { name })
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,17 +365,72 @@ pub struct ClientSecret {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Tool {
-    // TODO: !
+pub struct FunctionTool {
+    pub name: String,
+
+    pub description: String,
+
+    /// JSON schema of the function arguments
+    pub parameters: Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A tool available to the model
+/// See: https://platform.openai.com/docs/guides/realtime-conversations#function-calling
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Tool {
+    Function(FunctionTool),
+}
+
+impl Tool {
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+    ) -> Self {
+        Tool::Function(FunctionTool {
+            name: name.into(),
+            description: description.into(),
+            parameters,
+        })
+    }
+}
+
+/// Transcription of the user's audio, run asynchronously next to the model. Transcripts are
+/// reported as [`Event::InputTranscriptDelta`](crate::Event::InputTranscriptDelta) and
+/// [`Event::InputTranscriptDone`](crate::Event::InputTranscriptDone).
+/// See: https://platform.openai.com/docs/guides/realtime-transcription
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct InputAudioTranscription {
+    /// `whisper-1`, `gpt-4o-transcribe` or `gpt-4o-mini-transcribe`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Language of the audio as ISO-639-1 code, e.g. `en`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Text to guide the transcription, e.g. expected words
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+impl InputAudioTranscription {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: Some(model.into()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum Tracing {
     Auto,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct SessionUpdateEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub modalities: Option<Vec<Modality>>,
@@ -86,16 +457,135 @@ pub struct SessionUpdateEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tracing: Option<Tracing>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<ToolChoice>,
 
+    /// `Some(TurnDetection::None)` turns turn detection off
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub turn_detection: Option<TurnDetection>,
+
+    /// `Some(None)` turns transcription off
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_audio_transcription: Option<Option<InputAudioTranscription>>,
+
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_audio_noise_reduction: Option<NoiseReduction>,
+}
+
+impl SessionUpdateEvent {
+    /// Applies the fields set in `update` on top of this one
+    pub fn merge(&mut self, update: SessionUpdateEvent) {
+        let SessionUpdateEvent {
+            modalities,
+            instructions,
+            voice,
+            output_audio_format,
+            input_audio_format,
+            temperature,
+            speed,
+            tracing,
+            tools,
+            tool_choice,
+            turn_detection,
+            input_audio_transcription,
+            input_audio_noise_reduction,
+        } = update;
+        self.modalities = modalities.or(self.modalities.take());
+        self.instructions = instructions.or(self.instructions.take());
+        self.voice = voice.or(self.voice.take());
+        self.output_audio_format = output_audio_format.or(self.output_audio_format.take());
+        self.input_audio_format = input_audio_format.or(self.input_audio_format.take());
+        self.temperature = temperature.or(self.temperature.take());
+        self.speed = speed.or(self.speed.take());
+        self.tracing = tracing.or(self.tracing.take());
+        self.tools = tools.or(self.tools.take());
+        self.tool_choice = tool_choice.or(self.tool_choice.take());
+        self.turn_detection = turn_detection.or(self.turn_detection.take());
+        self.input_audio_transcription =
+            input_audio_transcription.or(self.input_audio_transcription.take());
+        self.input_audio_noise_reduction =
+            input_audio_noise_reduction.or(self.input_audio_noise_reduction.take());
+    }
+}
+
+/// See: https://platform.openai.com/docs/api-reference/realtime-client-events/transcription_session/update
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TranscriptionSessionUpdateEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_format: Option<AudioFormat>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_audio_transcription: Option<InputAudioTranscription>,
+
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub input_audio_noise_reduction: Option<NoiseReduction>,
+
+    /// `Some(TurnDetection::None)` turns turn detection off. `create_response` and
+    /// `interrupt_response` are not sent, transcription sessions never create responses.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        serialize_with = "serialize_transcription_turn_detection",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub turn_detection: Option<TurnDetection>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+}
+
+/// The transcription session resource
+/// See: https://platform.openai.com/docs/api-reference/realtime-sessions/transcription_session_object
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptionSessionResource {
+    #[serde(default)]
+    pub id: Option<String>,
+
+    #[serde(default)]
+    pub object: Option<String>,
+
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+
+    #[serde(default)]
+    pub input_audio_format: AudioFormat,
+
+    #[serde(default)]
+    pub input_audio_transcription: Option<InputAudioTranscription>,
+
+    #[serde(default)]
+    pub input_audio_noise_reduction: NoiseReduction,
+
+    pub turn_detection: TurnDetection,
+
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<ClientSecret>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
-#[serde(deny_unknown_fields)]
 pub struct Session {
     pub id: String,
 
@@ -103,16 +593,15 @@ pub struct Session {
 
     pub expires_at: i64,
 
-    // TODO: !
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub input_audio_noise_reduction: Option<Value>,
+    #[serde(default)]
+    pub input_audio_noise_reduction: NoiseReduction,
 
     pub turn_detection: TurnDetection,
 
     pub input_audio_format: AudioFormat,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub input_audio_transcription: Option<Value>,
+    pub input_audio_transcription: Option<InputAudioTranscription>,
 
     pub include: Value,
 
@@ -136,8 +625,134 @@ pub struct Session {
 
     pub tracing: Value,
 
-    pub tools: Tool,
+    pub tools: Vec<Tool>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub client_secret: Option<ClientSecret>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::session::{
+        Eagerness, InputAudioTranscription, NoiseReduction, SessionUpdateEvent, ToolChoice,
+        TurnDetection,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_choice_serde() {
+        for (choice, value) in [
+            (ToolChoice::Auto, json!("auto")),
+            (ToolChoice::None, json!("none")),
+            (ToolChoice::Required, json!("required")),
+            (
+                ToolChoice::Function {
+                    name: "get_weather".into(),
+                },
+                json!({ "type": "function", "name": "get_weather" }),
+            ),
+        ] {
+            assert_eq!(serde_json::to_value(&choice).unwrap(), value);
+            assert_eq!(serde_json::from_value::<ToolChoice>(value).unwrap(), choice);
+        }
+    }
+
+    #[test]
+    fn test_turn_detection_serde() {
+        for (turn_detection, value) in [
+            (
+                TurnDetection::server_vad(),
+                json!({
+                    "type": "server_vad", "threshold": 0.5, "prefix_padding_ms": 300,
+                    "silence_duration_ms": 500
+                }),
+            ),
+            (
+                TurnDetection::SemanticVad {
+                    eagerness: Eagerness::High,
+                    create_response: Some(false),
+                    interrupt_response: Some(true),
+                },
+                json!({
+                    "type": "semantic_vad", "eagerness": "high",
+                    "create_response": false, "interrupt_response": true
+                }),
+            ),
+            (TurnDetection::None, json!(null)),
+            // new VAD types don't break decoding
+            (
+                TurnDetection::Other(json!({ "type": "future_vad", "sensitivity": 2 })),
+                json!({ "type": "future_vad", "sensitivity": 2 }),
+            ),
+        ] {
+            assert_eq!(serde_json::to_value(&turn_detection).unwrap(), value);
+            assert_eq!(
+                serde_json::from_value::<TurnDetection>(value.clone()).unwrap(),
+                turn_detection
+            );
+        }
+
+        // turning it off sends an explicit null
+        let update = SessionUpdateEvent {
+            turn_detection: Some(TurnDetection::None),
+            ..Default::default()
+        };
+        let update = serde_json::to_value(update).unwrap();
+        assert_eq!(update, json!({ "turn_detection": null }));
+        let update = serde_json::from_value::<SessionUpdateEvent>(update).unwrap();
+        assert_eq!(update.turn_detection, Some(TurnDetection::None));
+    }
+
+    #[test]
+    fn test_noise_reduction_serde() {
+        for (noise_reduction, value) in [
+            (NoiseReduction::NearField, json!({ "type": "near_field" })),
+            (NoiseReduction::FarField, json!({ "type": "far_field" })),
+            (NoiseReduction::Disabled, json!(null)),
+        ] {
+            assert_eq!(serde_json::to_value(noise_reduction).unwrap(), value);
+            assert_eq!(
+                serde_json::from_value::<NoiseReduction>(value.clone()).unwrap(),
+                noise_reduction
+            );
+
+            // an explicit null disables it, a missing field leaves it unchanged
+            let update = SessionUpdateEvent {
+                input_audio_noise_reduction: Some(noise_reduction),
+                ..Default::default()
+            };
+            let update = serde_json::to_value(update).unwrap();
+            assert_eq!(update["input_audio_noise_reduction"], value);
+            let update = serde_json::from_value::<SessionUpdateEvent>(update).unwrap();
+            assert_eq!(update.input_audio_noise_reduction, Some(noise_reduction));
+        }
+        let update = serde_json::from_value::<SessionUpdateEvent>(json!({})).unwrap();
+        assert_eq!(update.input_audio_noise_reduction, None);
+    }
+
+    #[test]
+    fn test_input_transcription_serde() {
+        let transcription = InputAudioTranscription::new("gpt-4o-transcribe");
+        for (input_audio_transcription, value) in [
+            (Some(transcription), json!({ "model": "gpt-4o-transcribe" })),
+            (None, json!(null)),
+        ] {
+            // an explicit null disables it, a missing field leaves it unchanged
+            let update = SessionUpdateEvent {
+                input_audio_transcription: Some(input_audio_transcription.clone()),
+                ..Default::default()
+            };
+            let update = serde_json::to_value(update).unwrap();
+            assert_eq!(update["input_audio_transcription"], value);
+            let update = serde_json::from_value::<SessionUpdateEvent>(update).unwrap();
+            assert_eq!(
+                update.input_audio_transcription,
+                Some(input_audio_transcription)
+            );
+        }
+        let update = serde_json::to_value(SessionUpdateEvent::default()).unwrap();
+        assert!(update.get("input_audio_transcription").is_none());
+        let update = serde_json::from_value::<SessionUpdateEvent>(update).unwrap();
+        assert_eq!(update.input_audio_transcription, None);
+    }
+}
@@ -12,16 +12,34 @@ pub enum Voice {
     Sage,
     Shimmer,
     Verse,
+    /// A voice not known to this crate, sent as is
+    #[serde(untagged)]
+    Other(String),
 }
 
 impl From<&str> for Voice {
     fn from(s: &str) -> Self {
-        serde_json::from_str(format!("\"{}\"", s).as_str()).unwrap()
+        s.to_string().into()
     }
 }
 
 impl From<String> for Voice {
     fn from(s: String) -> Self {
-        serde_json::from_str(format!("\"{}\"", s).as_str()).unwrap()
+        serde_json::from_value(serde_json::Value::String(s.clone())).unwrap_or(Voice::Other(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::voice::Voice;
+
+    #[test]
+    fn test_voice_from_str() {
+        assert!(matches!(Voice::from("verse"), Voice::Verse));
+        assert!(matches!(Voice::from("marin"), Voice::Other(name) if name == "marin"));
+        assert_eq!(
+            serde_json::to_string(&Voice::from("marin")).unwrap(),
+            "\"marin\""
+        );
     }
 }
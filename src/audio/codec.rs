@@ -0,0 +1,260 @@
+//! Conversion between PCM16 and G.711 μ-law/A-law.
+//! See: https://www.itu.int/rec/T-REC-G.711
+
+use crate::api::session::AudioFormat;
+use crate::audio::resample::Resampler;
+use std::time::Duration;
+
+const ULAW_BIAS: i32 = 0x84;
+const ULAW_CLIP: i32 = 32635;
+
+/// Encodes a 16-bit sample as μ-law
+pub fn encode_ulaw(sample: i16) -> u8 {
+    let mut value = sample as i32;
+    let sign = if value < 0 {
+        value = -value;
+        0x80
+    } else {
+        0
+    };
+    value = value.min(ULAW_CLIP) + ULAW_BIAS;
+    let exponent = (value >> 7).max(1).ilog2() as i32;
+    let mantissa = (value >> (exponent + 3)) & 0x0f;
+    !(sign | (exponent << 4) | mantissa) as u8
+}
+
+/// Decodes a μ-law byte to a 16-bit sample
+pub fn decode_ulaw(byte: u8) -> i16 {
+    let byte = !byte as i32;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = byte & 0x0f;
+    let value = (((mantissa << 3) + ULAW_BIAS) << exponent) - ULAW_BIAS;
+    if byte & 0x80 != 0 {
+        -value as i16
+    } else {
+        value as i16
+    }
+}
+
+/// Encodes a 16-bit sample as A-law
+pub fn encode_alaw(sample: i16) -> u8 {
+    let mut value = (sample as i32) >> 3;
+    let mask = if value >= 0 {
+        0xd5
+    } else {
+        value = -value - 1;
+        0x55
+    };
+    let segment = if value < 0x20 {
+        0
+    } else {
+        value.ilog2() as i32 - 4
+    };
+    let byte = match segment {
+        0 => value >> 1,
+        8.. => 0x7f,
+        _ => (segment << 4) | ((value >> segment) & 0x0f),
+    };
+    (byte ^ mask) as u8
+}
+
+/// Decodes an A-law byte to a 16-bit sample
+pub fn decode_alaw(byte: u8) -> i16 {
+    let byte = (byte ^ 0x55) as i32;
+    let segment = (byte & 0x70) >> 4;
+    let mut value = (byte & 0x0f) << 4;
+    value += match segment {
+        0 => 8,
+        _ => 0x108,
+    };
+    if segment > 1 {
+        value <<= segment - 1;
+    }
+    if byte & 0x80 != 0 {
+        value as i16
+    } else {
+        -value as i16
+    }
+}
+
+/// Decodes audio in `format` to samples at the format's sample rate
+pub fn decode(format: AudioFormat, bytes: &[u8]) -> Vec<i16> {
+    match format {
+        AudioFormat::PCM16 => bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect(),
+        AudioFormat::G711Ulaw => bytes.iter().map(|b| decode_ulaw(*b)).collect(),
+        AudioFormat::G711Alaw => bytes.iter().map(|b| decode_alaw(*b)).collect(),
+    }
+}
+
+/// Encodes samples at the format's sample rate in `format`
+pub fn encode(format: AudioFormat, samples: &[i16]) -> Vec<u8> {
+    match format {
+        AudioFormat::PCM16 => samples.iter().flat_map(|s| s.to_le_bytes()).collect(),
+        AudioFormat::G711Ulaw => samples.iter().map(|s| encode_ulaw(*s)).collect(),
+        AudioFormat::G711Alaw => samples.iter().map(|s| encode_alaw(*s)).collect(),
+    }
+}
+
+/// `duration` of silence in `format`
+pub fn silence(format: AudioFormat, duration: Duration) -> Vec<u8> {
+    let samples = (format.sample_rate() as u128 * duration.as_millis() / 1000) as usize;
+    encode(format, &vec![0; samples])
+}
+
+/// Converts a complete recording from one format to another, including its sample rate.
+/// Use a [`Transcoder`] for audio that arrives in chunks.
+pub fn transcode(from: AudioFormat, to: AudioFormat, bytes: &[u8]) -> Vec<u8> {
+    let mut transcoder = Transcoder::new(from, to);
+    let mut output = transcoder.process(bytes);
+    output.extend(transcoder.flush());
+    output
+}
+
+/// Streaming conversion from one format to another. The sample rate is converted by a
+/// [`Resampler`], which carries its position and filter state across chunks, so chunks are joined
+/// seamlessly. The output lags the input by a few milliseconds until [`flush`](Self::flush).
+#[derive(Debug, Clone)]
+pub struct Transcoder {
+    from: AudioFormat,
+    to: AudioFormat,
+    resampler: Resampler,
+    /// First byte of a PCM16 sample split across chunks
+    remainder: Option<u8>,
+}
+
+impl Transcoder {
+    pub fn new(from: AudioFormat, to: AudioFormat) -> Self {
+        Self {
+            from,
+            to,
+            resampler: Resampler::new(from.sample_rate(), to.sample_rate()),
+            remainder: None,
+        }
+    }
+
+    pub fn from_format(&self) -> AudioFormat {
+        self.from
+    }
+
+    pub fn to_format(&self) -> AudioFormat {
+        self.to
+    }
+
+    /// Converts the next chunk. Chunks may split a sample, its bytes are joined with the next
+    /// chunk.
+    pub fn process(&mut self, bytes: &[u8]) -> Vec<u8> {
+        if self.from == self.to {
+            return bytes.to_vec();
+        }
+        let joined;
+        let mut bytes = match self.remainder.take() {
+            Some(byte) => {
+                joined = [&[byte], bytes].concat();
+                &joined[..]
+            }
+            None => bytes,
+        };
+        if bytes.len() % self.from.bytes_per_sample() != 0 {
+            let (last, rest) = bytes.split_last().unwrap();
+            self.remainder = Some(*last);
+            bytes = rest;
+        }
+        let samples = self.resampler.process(&decode(self.from, bytes));
+        encode(self.to, &samples)
+    }
+
+    /// Returns the audio held back by the resampler and starts over, e.g. at the end of an
+    /// audio content part. An incomplete sample is dropped.
+    pub fn flush(&mut self) -> Vec<u8> {
+        self.remainder = None;
+        encode(self.to, &self.resampler.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::session::AudioFormat;
+    use crate::audio::codec::{
+        Transcoder, decode_alaw, decode_ulaw, encode_alaw, encode_ulaw, transcode,
+    };
+
+    #[test]
+    fn test_g711_round_trip() {
+        assert_eq!(encode_ulaw(0), 0xff);
+        assert_eq!(encode_alaw(0), 0xd5);
+        assert_eq!(decode_ulaw(0xff), 0);
+        assert_eq!(decode_alaw(0xd5), 8);
+
+        for sample in (i16::MIN..=i16::MAX).step_by(7) {
+            // the quantization step grows with the magnitude, 4 bits of mantissa
+            let tolerance = (sample as i32).abs() / 16 + 16;
+            for (encoded, decoded) in [
+                (encode_ulaw(sample), decode_ulaw(encode_ulaw(sample))),
+                (encode_alaw(sample), decode_alaw(encode_alaw(sample))),
+            ] {
+                let error = (decoded as i32 - sample as i32).abs();
+                assert!(
+                    error <= tolerance.max(132),
+                    "{sample} -> {encoded:#04x} -> {decoded}"
+                );
+            }
+        }
+
+        // every code decodes to a value that encodes back to it, except for μ-law's negative zero
+        for byte in 0..=255u8 {
+            if byte != 0x7f {
+                assert_eq!(encode_ulaw(decode_ulaw(byte)), byte, "{byte:#04x}");
+            }
+            assert_eq!(encode_alaw(decode_alaw(byte)), byte, "{byte:#04x}");
+        }
+    }
+
+    #[test]
+    fn test_transcode() {
+        // 10ms of PCM16 at 24 kHz become 10ms of G.711 at 8 kHz and back
+        let pcm16: Vec<u8> = (0..240i16).flat_map(|i| (i * 100).to_le_bytes()).collect();
+        let ulaw = transcode(AudioFormat::PCM16, AudioFormat::G711Ulaw, &pcm16);
+        assert_eq!(ulaw.len(), 80);
+        let alaw = transcode(AudioFormat::G711Ulaw, AudioFormat::G711Alaw, &ulaw);
+        assert_eq!(alaw.len(), 80);
+        let back = transcode(AudioFormat::G711Alaw, AudioFormat::PCM16, &alaw);
+        assert_eq!(back.len(), pcm16.len());
+        assert_eq!(
+            transcode(AudioFormat::PCM16, AudioFormat::PCM16, &pcm16),
+            pcm16
+        );
+    }
+
+    #[test]
+    fn test_transcoder() {
+        // 100ms of a 440 Hz tone, converted in chunks of 10ms
+        let pcm16: Vec<u8> = (0..2400)
+            .map(|i| {
+                ((i as f64 * 440.0 / 24_000.0 * std::f64::consts::TAU).sin() * 10_000.0) as i16
+            })
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let mut transcoder = Transcoder::new(AudioFormat::PCM16, AudioFormat::G711Ulaw);
+        let mut ulaw = vec![];
+        for chunk in pcm16.chunks(480) {
+            ulaw.extend(transcoder.process(chunk));
+        }
+        ulaw.extend(transcoder.flush());
+        assert_eq!(ulaw.len(), 800);
+        assert_eq!(
+            ulaw,
+            transcode(AudioFormat::PCM16, AudioFormat::G711Ulaw, &pcm16)
+        );
+
+        // chunks splitting samples at odd offsets give the same result
+        let mut split = vec![];
+        for chunk in pcm16.chunks(481) {
+            split.extend(transcoder.process(chunk));
+        }
+        split.extend(transcoder.flush());
+        assert_eq!(split, ulaw);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod codec;
+pub mod pipeline;
+pub mod resample;
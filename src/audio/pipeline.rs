@@ -0,0 +1,307 @@
+//! Audio in the device's native format: interleaved 16-bit PCM at any sample rate and channel
+//! count, converted to and from the session's audio formats.
+
+use crate::api::session::AudioFormat;
+use crate::audio::codec;
+use crate::audio::resample::Resampler;
+use crate::error::RealtimeError;
+use crate::websocket::{AudioReceiver, EventHandle, RealtimeSession};
+use std::sync::Arc;
+
+/// Interleaved 16-bit PCM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl PcmFormat {
+    pub fn mono(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: 1,
+        }
+    }
+
+    pub fn stereo(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: 2,
+        }
+    }
+}
+
+/// Sends audio in a native format, e.g. a microphone's, to the session's input audio buffer.
+/// Channels are averaged to mono, resampled and encoded in the session's `input_audio_format`.
+pub struct AudioInput {
+    session: Arc<RealtimeSession>,
+    format: PcmFormat,
+    resampler: Resampler,
+    /// Bytes of a frame split across chunks passed to `append_bytes`
+    remainder: Vec<u8>,
+}
+
+impl AudioInput {
+    pub fn new(session: Arc<RealtimeSession>, format: PcmFormat) -> Self {
+        let resampler = Resampler::new(
+            format.sample_rate,
+            session.input_audio_format().sample_rate(),
+        );
+        Self {
+            session,
+            format,
+            resampler,
+            remainder: Vec::new(),
+        }
+    }
+
+    pub fn format(&self) -> PcmFormat {
+        self.format
+    }
+
+    /// Appends interleaved samples. The resampler holds back a few milliseconds, which are sent
+    /// with the next chunk or by [`flush`](Self::flush).
+    pub fn append(&mut self, samples: &[i16]) -> Result<EventHandle, RealtimeError> {
+        let target = self.session.input_audio_format();
+        if self.resampler.to_rate() != target.sample_rate() {
+            self.resampler = Resampler::new(self.format.sample_rate, target.sample_rate());
+        }
+        let mono = downmix(samples, self.format.channels);
+        let samples = self.resampler.process(&mono);
+        self.session.audio_append(codec::encode(target, &samples))
+    }
+
+    /// Appends interleaved little-endian bytes. Chunks may split a frame, its bytes are joined
+    /// with the next chunk.
+    pub fn append_bytes(&mut self, bytes: &[u8]) -> Result<EventHandle, RealtimeError> {
+        let frame = 2 * self.format.channels.max(1) as usize;
+        let mut bytes = [self.remainder.as_slice(), bytes].concat();
+        self.remainder = bytes.split_off(bytes.len() - bytes.len() % frame);
+        let samples: Vec<i16> = bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        self.append(&samples)
+    }
+
+    /// Sends the audio held back by the resampler, e.g. before committing the input audio buffer.
+    /// An incomplete frame is dropped.
+    pub fn flush(&mut self) -> Result<EventHandle, RealtimeError> {
+        self.remainder.clear();
+        let target = self.session.input_audio_format();
+        let samples = self.resampler.flush();
+        self.session.audio_append(codec::encode(target, &samples))
+    }
+}
+
+/// Receives the assistant's audio in a native format, e.g. a speaker's. The audio is decoded,
+/// resampled and copied to every channel.
+pub struct AudioOutput {
+    session: Arc<RealtimeSession>,
+    receiver: AudioReceiver,
+    format: PcmFormat,
+    /// generation and format of the audio received last
+    source: Option<(u64, AudioFormat)>,
+    resampler: Resampler,
+    /// frames acknowledged with `played` since the audio started over, and the session's
+    /// samples reported for them
+    played_frames: u64,
+    reported_samples: u64,
+}
+
+impl AudioOutput {
+    pub fn new(
+        session: Arc<RealtimeSession>,
+        mut receiver: AudioReceiver,
+        format: PcmFormat,
+    ) -> Self {
+        // played samples are reported in the session's `output_audio_format`
+        receiver.set_format(None);
+        let resampler = Resampler::new(
+            session.output_audio_format().sample_rate(),
+            format.sample_rate,
+        );
+        Self {
+            session,
+            receiver,
+            format,
+            source: None,
+            resampler,
+            played_frames: 0,
+            reported_samples: 0,
+        }
+    }
+
+    pub fn format(&self) -> PcmFormat {
+        self.format
+    }
+
+    /// Receives the next chunk of interleaved samples, `None` once the session is closed
+    pub async fn recv(&mut self) -> Option<Vec<i16>> {
+        loop {
+            let (generation, format, audio) = self.receiver.recv_chunk().await?;
+            // an interruption or another format starts over
+            if self.source != Some((generation, format)) {
+                self.source = Some((generation, format));
+                self.resampler = Resampler::new(format.sample_rate(), self.format.sample_rate);
+                self.played_frames = 0;
+                self.reported_samples = 0;
+            }
+            // an empty chunk ends the audio content part
+            let samples = if audio.is_empty() {
+                self.resampler.flush()
+            } else {
+                self.resampler.process(&codec::decode(format, &audio))
+            };
+            if !samples.is_empty() {
+                return Some(upmix(&samples, self.format.channels));
+            }
+        }
+    }
+
+    /// Acknowledges `frames` frames as played by the sink, see [`RealtimeSession::audio_played`]
+    pub fn played(&mut self, frames: usize) {
+        self.played_frames += frames as u64;
+        let samples =
+            self.played_frames * self.resampler.from_rate() as u64 / self.format.sample_rate as u64;
+        self.session
+            .audio_played((samples.saturating_sub(self.reported_samples)) as usize);
+        self.reported_samples = samples;
+    }
+}
+
+/// Averages the channels of interleaved samples
+fn downmix(samples: &[i16], channels: u16) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(channels as usize)
+        .map(|frame| (frame.iter().map(|s| *s as i32).sum::<i32>() / channels as i32) as i16)
+        .collect()
+}
+
+/// Copies mono samples to every channel
+fn upmix(samples: &[i16], channels: u16) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .iter()
+        .flat_map(|s| std::iter::repeat_n(*s, channels as usize))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::client_event::ClientEvent;
+    use crate::api::server_event::{ResponseAudioDoneEvent, ResponseDeltaEvent, ServerEvent};
+    use crate::api::session::{AudioFormat, SessionUpdateEvent};
+    use crate::audio::pipeline::{AudioInput, AudioOutput, PcmFormat};
+    use crate::event::Event;
+    use crate::testing::MockServer;
+    use crate::websocket::connect;
+    use base64::prelude::*;
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let mock = MockServer::start().await;
+        let (session, rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        session
+            .session_update(SessionUpdateEvent {
+                input_audio_format: Some(AudioFormat::G711Alaw),
+                ..Default::default()
+            })
+            .unwrap();
+        while !matches!(
+            events.recv().await.unwrap(),
+            Event::Server(ServerEvent::SessionUpdated(_))
+        ) {}
+
+        // 100ms of 48 kHz stereo become 100ms of 8 kHz A-law
+        let mut input = AudioInput::new(session.clone(), PcmFormat::stereo(48_000));
+        input.append(&[0; 9600]).unwrap();
+        input.flush().unwrap();
+        session.input_audio_buffer_commit().unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::InputAudioBufferCommit))
+            .await
+            .unwrap();
+        let mut sent = 0;
+        for evt in mock.received() {
+            if let ClientEvent::InputAudioBufferAppend { audio } = evt {
+                let audio = BASE64_STANDARD.decode(audio).unwrap();
+                assert!(audio.iter().all(|b| *b == 0xd5));
+                sent += audio.len();
+            }
+        }
+        assert_eq!(sent, 800);
+
+        // bytes split at odd offsets, even within a frame
+        let bytes = 1000i16.to_le_bytes().repeat(9600);
+        for chunk in bytes.chunks(333) {
+            input.append_bytes(chunk).unwrap();
+        }
+        input.flush().unwrap();
+        session.input_audio_buffer_clear().unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::InputAudioBufferClear))
+            .await
+            .unwrap();
+        let mut sent = 0;
+        for evt in mock
+            .received()
+            .into_iter()
+            .skip_while(|evt| !matches!(evt, ClientEvent::InputAudioBufferCommit))
+        {
+            if let ClientEvent::InputAudioBufferAppend { audio } = evt {
+                sent += BASE64_STANDARD.decode(audio).unwrap().len();
+            }
+        }
+        assert_eq!(sent, 800);
+
+        // 100ms of 24 kHz PCM16 become 44.1 kHz stereo, lagging by the resampler's filter
+        let mut output = AudioOutput::new(session.clone(), rx_audio, PcmFormat::stereo(44_100));
+        mock.send(ServerEvent::ResponseAudioDelta(ResponseDeltaEvent {
+            event_id: "event_1".to_string(),
+            response_id: "resp_1".to_string(),
+            item_id: "item_1".to_string(),
+            output_index: 0,
+            content_index: 0,
+            delta: BASE64_STANDARD.encode([0; 4800]),
+        }));
+        let mut samples = output.recv().await.unwrap();
+        assert_eq!(samples.len() % 2, 0);
+        assert!((8700..8820).contains(&samples.len()));
+        output.played(samples.len() / 2);
+
+        // the end of the audio flushes the resampler
+        mock.send(ServerEvent::ResponseAudioDone(ResponseAudioDoneEvent {
+            event_id: "event_2".to_string(),
+            response_id: "resp_1".to_string(),
+            item_id: "item_1".to_string(),
+            output_index: 0,
+            content_index: 0,
+        }));
+        samples.extend(output.recv().await.unwrap());
+        assert_eq!(samples.len(), 8820);
+
+        // an interruption drops what the resampler holds back
+        let delta = |audio: Vec<u8>| {
+            ServerEvent::ResponseAudioDelta(ResponseDeltaEvent {
+                event_id: "event_3".to_string(),
+                response_id: "resp_2".to_string(),
+                item_id: "item_2".to_string(),
+                output_index: 0,
+                content_index: 0,
+                delta: BASE64_STANDARD.encode(audio),
+            })
+        };
+        mock.send(delta(1000i16.to_le_bytes().repeat(2400)));
+        assert!(output.recv().await.unwrap().iter().any(|s| *s != 0));
+        session.interrupt().unwrap();
+        mock.send(delta(vec![0; 4800]));
+        let samples = output.recv().await.unwrap();
+        assert!((8700..8820).contains(&samples.len()));
+        assert!(samples.iter().all(|s| *s == 0));
+    }
+}
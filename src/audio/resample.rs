@@ -0,0 +1,181 @@
+//! Band-limited sample rate conversion of mono 16-bit audio by windowed-sinc interpolation.
+
+use std::f64::consts::PI;
+
+/// Zero crossings of the sinc on each side of a tap, at the lower of the two rates
+const ZERO_CROSSINGS: f64 = 16.0;
+
+/// Fraction of the lower Nyquist frequency kept, leaves room for the filter's transition band
+const PASSBAND: f64 = 0.95;
+
+/// Streaming resampler. Chunks passed to [`process`](Self::process) are joined seamlessly,
+/// the output lags the input by the filter's half width until [`flush`](Self::flush).
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    from: u32,
+    to: u32,
+    /// input samples per output sample
+    step: f64,
+    /// cutoff relative to the input Nyquist frequency
+    cutoff: f64,
+    /// filter half width in input samples
+    half_width: i64,
+    buffer: Vec<f64>,
+    /// index of the input sample at `buffer[0]`, negative for the leading silence
+    start: i64,
+    /// output samples produced so far
+    produced: u64,
+}
+
+impl Resampler {
+    pub fn new(from: u32, to: u32) -> Self {
+        let cutoff = (to as f64 / from as f64).min(1.0) * PASSBAND;
+        let half_width = (ZERO_CROSSINGS / cutoff).ceil() as i64;
+        Self {
+            from,
+            to,
+            step: from as f64 / to as f64,
+            cutoff,
+            half_width,
+            buffer: vec![0.0; half_width as usize],
+            start: -half_width,
+            produced: 0,
+        }
+    }
+
+    pub fn from_rate(&self) -> u32 {
+        self.from
+    }
+
+    pub fn to_rate(&self) -> u32 {
+        self.to
+    }
+
+    /// Resamples the next chunk of input
+    pub fn process(&mut self, input: &[i16]) -> Vec<i16> {
+        if self.from == self.to {
+            return input.to_vec();
+        }
+        self.buffer.extend(input.iter().map(|s| *s as f64));
+        let end = self.start + self.buffer.len() as i64;
+        let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+        while (self.time().floor() as i64) + self.half_width < end {
+            output.push(self.sample_at(self.time()));
+            self.produced += 1;
+        }
+        // keep what the next output sample needs
+        let keep_from = self.time().floor() as i64 + 1 - self.half_width;
+        let consumed = (keep_from - self.start).clamp(0, self.buffer.len() as i64);
+        self.buffer.drain(..consumed as usize);
+        self.start += consumed;
+        output
+    }
+
+    /// Returns the output still held back and resets the resampler
+    pub fn flush(&mut self) -> Vec<i16> {
+        if self.from == self.to {
+            return vec![];
+        }
+        let end = (self.start + self.buffer.len() as i64) as f64;
+        self.buffer
+            .extend(std::iter::repeat_n(0.0, self.half_width as usize + 1));
+        let mut output = vec![];
+        while self.time() < end {
+            output.push(self.sample_at(self.time()));
+            self.produced += 1;
+        }
+        *self = Self::new(self.from, self.to);
+        output
+    }
+
+    /// Input time of the next output sample
+    fn time(&self) -> f64 {
+        self.produced as f64 * self.step
+    }
+
+    fn sample_at(&self, time: f64) -> i16 {
+        let center = time.floor() as i64;
+        let sum: f64 = (center + 1 - self.half_width..=center + self.half_width)
+            .map(|i| self.buffer[(i - self.start) as usize] * self.kernel(time - i as f64))
+            .sum();
+        sum.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+    }
+
+    /// Low-pass sinc at the cutoff, shaped by a Blackman window
+    fn kernel(&self, x: f64) -> f64 {
+        let u = x / self.half_width as f64;
+        if u.abs() >= 1.0 {
+            return 0.0;
+        }
+        let window = 0.42 + 0.5 * (PI * u).cos() + 0.08 * (2.0 * PI * u).cos();
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (PI * self.cutoff * x).sin() / (PI * self.cutoff * x)
+        };
+        self.cutoff * sinc * window
+    }
+}
+
+/// Resamples a complete recording
+pub fn resample(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    let mut resampler = Resampler::new(from, to);
+    let mut output = resampler.process(samples);
+    output.extend(resampler.flush());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audio::resample::{Resampler, resample};
+    use std::f64::consts::PI;
+
+    fn sine(frequency: f64, rate: u32, len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| ((2.0 * PI * frequency * i as f64 / rate as f64).sin() * 10_000.0) as i16)
+            .collect()
+    }
+
+    fn rms(samples: &[i16]) -> f64 {
+        (samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_resample() {
+        for (from, to) in [
+            (16_000, 24_000),
+            (44_100, 24_000),
+            (48_000, 24_000),
+            (24_000, 8_000),
+        ] {
+            let input = sine(440.0, from, from as usize / 10);
+            let output = resample(&input, from, to);
+            assert_eq!(output.len(), to as usize / 10, "{from} -> {to}");
+
+            // a tone in the passband keeps its level and its shape
+            let expected = sine(440.0, to, output.len());
+            let middle = output.len() / 4..output.len() * 3 / 4;
+            let error: Vec<i16> = middle
+                .clone()
+                .map(|i| output[i].saturating_sub(expected[i]))
+                .collect();
+            assert!(rms(&error) < 100.0, "{from} -> {to}: {}", rms(&error));
+        }
+
+        // a tone above the new Nyquist frequency is removed instead of aliased
+        let output = resample(&sine(10_000.0, 48_000, 4800), 48_000, 16_000);
+        assert!(rms(&output[400..1200]) < 100.0);
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let input = sine(300.0, 44_100, 4410);
+        let mut resampler = Resampler::new(44_100, 24_000);
+        let mut output = vec![];
+        for chunk in input.chunks(441) {
+            output.extend(resampler.process(chunk));
+        }
+        output.extend(resampler.flush());
+        assert_eq!(output, resample(&input, 44_100, 24_000));
+    }
+}
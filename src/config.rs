@@ -1,9 +1,18 @@
-use std::fmt::Display;
+use crate::api::model::Model;
+use crate::error::RealtimeError;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use url::Url;
 
 #[derive(Debug, Clone)]
 pub enum ApiKeyRef {
     Value(String),
     Env(Option<String>),
+    /// Asked for a key before every HTTP request and connection attempt, e.g. for short-lived
+    /// Microsoft Entra ID access tokens
+    Provider(TokenProvider),
 }
 
 impl Display for ApiKeyRef {
@@ -11,21 +20,68 @@ impl Display for ApiKeyRef {
         match self {
             ApiKeyRef::Value(key) => write!(f, "{}", key),
             ApiKeyRef::Env(env) => write!(f, "{}", env.as_deref().unwrap_or("OPENAI_KEY")),
+            ApiKeyRef::Provider(_) => write!(f, "token provider"),
         }
     }
 }
 
 impl ApiKeyRef {
     /// Resolve the Api-Key
-    pub fn api_key(&self) -> String {
+    pub async fn api_key(&self) -> Result<String, RealtimeError> {
         match &self {
-            ApiKeyRef::Value(key) => key.to_string(),
+            ApiKeyRef::Value(key) => Ok(key.to_string()),
             ApiKeyRef::Env(env) => {
                 let env_key = env.clone().unwrap_or("OPENAI_KEY".to_string());
-                std::env::var(env_key).unwrap_or_else(|_| "".to_string())
+                Ok(std::env::var(env_key).unwrap_or_else(|_| "".to_string()))
             }
+            ApiKeyRef::Provider(provider) => provider.token().await,
         }
     }
+
+    /// The header carrying the resolved key, as expected by `provider`
+    pub async fn auth_header(
+        &self,
+        provider: &Provider,
+    ) -> Result<(&'static str, String), RealtimeError> {
+        let key = self.api_key().await?;
+        Ok(match provider {
+            Provider::Azure {
+                auth: AzureAuth::ApiKey,
+                ..
+            } => ("api-key", key),
+            Provider::OpenAI
+            | Provider::Azure {
+                auth: AzureAuth::EntraId,
+                ..
+            } => ("Authorization", format!("Bearer {key}")),
+        })
+    }
+}
+
+type TokenFuture = Pin<Box<dyn Future<Output = Result<String, RealtimeError>> + Send>>;
+
+/// Fetches a key or token on demand, see [`ApiKeyRef::Provider`]
+#[derive(Clone)]
+pub struct TokenProvider(Arc<dyn Fn() -> TokenFuture + Send + Sync>);
+
+impl TokenProvider {
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, RealtimeError>> + Send + 'static,
+    {
+        Self(Arc::new(move || Box::pin(f())))
+    }
+
+    pub async fn token(&self) -> Result<String, RealtimeError> {
+        (self.0)().await
+    }
+}
+
+impl Debug for TokenProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TokenProvider")
+    }
 }
 
 impl Default for ApiKeyRef {
@@ -33,3 +89,259 @@ impl Default for ApiKeyRef {
         Self::Env("OPENAI_KEY".to_string().into())
     }
 }
+
+/// How the key is presented to Azure OpenAI
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AzureAuth {
+    /// Resource key, sent as `api-key` header
+    #[default]
+    ApiKey,
+    /// Microsoft Entra ID access token, sent as bearer token. Tokens expire, so use an
+    /// [`ApiKeyRef::Provider`] to fetch a fresh one for every connection.
+    EntraId,
+}
+
+/// The flavor of the API behind an [`Endpoint`]
+#[derive(Debug, Clone, Default)]
+pub enum Provider {
+    #[default]
+    OpenAI,
+    /// Azure OpenAI: the deployment takes the place of the model
+    /// See: https://learn.microsoft.com/en-us/azure/ai-services/openai/how-to/realtime-audio-websockets
+    Azure {
+        deployment: String,
+        api_version: String,
+        auth: AzureAuth,
+    },
+}
+
+/// Where the API is reached. Shared by the HTTP and websocket clients, so both talk to the same
+/// host with the same extra headers and query parameters.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    /// Base URL of the API. The scheme is mapped per transport, so `https://host/v1` is
+    /// reached as `wss://host/v1` by the websocket and `ws://`/`http://` work the same way.
+    pub base_url: Url,
+    /// Extra headers sent with every request and websocket handshake
+    pub headers: Vec<(String, String)>,
+    /// Extra query parameters appended to every URL
+    pub query: Vec<(String, String)>,
+    pub provider: Provider,
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Self::new(Url::parse("https://api.openai.com/v1").unwrap())
+    }
+}
+
+impl Endpoint {
+    pub fn new(base_url: Url) -> Self {
+        Self {
+            base_url,
+            headers: vec![],
+            query: vec![],
+            provider: Provider::OpenAI,
+        }
+    }
+
+    /// Azure OpenAI resource `https://{resource}.openai.azure.com`, authenticated with its API key
+    pub fn azure(
+        resource: &str,
+        deployment: impl Into<String>,
+        api_version: impl Into<String>,
+    ) -> Result<Self, RealtimeError> {
+        let base_url = Url::parse(&format!("https://{resource}.openai.azure.com/openai"))
+            .map_err(|e| RealtimeError::InvalidConfig(format!("azure resource {resource}: {e}")))?;
+        Ok(Self {
+            provider: Provider::Azure {
+                deployment: deployment.into(),
+                api_version: api_version.into(),
+                auth: AzureAuth::ApiKey,
+            },
+            ..Self::new(base_url)
+        })
+    }
+
+    /// Websocket URL of a realtime session with `model`
+    pub fn realtime_url(&self, model: &Model) -> Url {
+        let mut url = self.websocket_url("realtime");
+        match &self.provider {
+            Provider::OpenAI => {
+                url.query_pairs_mut()
+                    .append_pair("model", &model.to_string());
+            }
+            Provider::Azure {
+                deployment,
+                api_version,
+                ..
+            } => {
+                url.query_pairs_mut()
+                    .append_pair("api-version", api_version)
+                    .append_pair("deployment", deployment);
+            }
+        }
+        url
+    }
+
+    /// Websocket URL of a transcription session, which transcribes audio without generating
+    /// responses
+    pub fn transcription_url(&self) -> Url {
+        let mut url = self.websocket_url("realtime");
+        if let Provider::Azure { api_version, .. } = &self.provider {
+            url.query_pairs_mut()
+                .append_pair("api-version", api_version);
+        }
+        url.query_pairs_mut().append_pair("intent", "transcription");
+        url
+    }
+
+    /// URL to create transcription sessions and their ephemeral tokens
+    pub fn transcription_sessions_url(&self) -> Url {
+        match &self.provider {
+            Provider::OpenAI => self.http_url("realtime/transcription_sessions"),
+            Provider::Azure { api_version, .. } => {
+                let mut url = self.http_url("realtimeapi/transcription_sessions");
+                url.query_pairs_mut()
+                    .append_pair("api-version", api_version);
+                url
+            }
+        }
+    }
+
+    /// URL to create sessions and ephemeral tokens
+    pub fn sessions_url(&self) -> Url {
+        match &self.provider {
+            Provider::OpenAI => self.http_url("realtime/sessions"),
+            Provider::Azure { api_version, .. } => {
+                let mut url = self.http_url("realtimeapi/sessions");
+                url.query_pairs_mut()
+                    .append_pair("api-version", api_version);
+                url
+            }
+        }
+    }
+
+    /// The model to request; on Azure this is the deployment
+    pub fn model(&self, model: &Model) -> Model {
+        match &self.provider {
+            Provider::OpenAI => model.clone(),
+            Provider::Azure { deployment, .. } => Model(deployment.clone()),
+        }
+    }
+
+    /// URL of `path` below the base URL, for HTTP requests
+    pub fn http_url(&self, path: &str) -> Url {
+        self.url(path, false)
+    }
+
+    /// URL of `path` below the base URL, for websocket connections
+    pub fn websocket_url(&self, path: &str) -> Url {
+        self.url(path, true)
+    }
+
+    fn url(&self, path: &str, websocket: bool) -> Url {
+        let mut url = self.base_url.clone();
+        url.set_path(&format!(
+            "{}/{}",
+            url.path().trim_end_matches('/'),
+            path.trim_start_matches('/')
+        ));
+        let scheme = match (url.scheme(), websocket) {
+            ("https" | "wss", true) => "wss",
+            ("http" | "ws", true) => "ws",
+            ("https" | "wss", false) => "https",
+            ("http" | "ws", false) => "http",
+            (scheme, _) => scheme,
+        }
+        .to_string();
+        // switching between special schemes always succeeds
+        let _ = url.set_scheme(&scheme);
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::model::Model;
+    use crate::config::{ApiKeyRef, AzureAuth, Endpoint, Provider, TokenProvider};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use url::Url;
+
+    #[test]
+    fn test_endpoint_urls() {
+        let endpoint = Endpoint {
+            query: vec![("tenant".to_string(), "acme".to_string())],
+            ..Endpoint::new(Url::parse("https://proxy.local/openai/v1/").unwrap())
+        };
+        assert_eq!(
+            endpoint.http_url("realtime/sessions").as_str(),
+            "https://proxy.local/openai/v1/realtime/sessions?tenant=acme"
+        );
+        assert_eq!(
+            endpoint.websocket_url("realtime").as_str(),
+            "wss://proxy.local/openai/v1/realtime?tenant=acme"
+        );
+        assert_eq!(
+            Endpoint::default().websocket_url("realtime").as_str(),
+            "wss://api.openai.com/v1/realtime"
+        );
+        assert_eq!(
+            Endpoint::default().transcription_url().as_str(),
+            "wss://api.openai.com/v1/realtime?intent=transcription"
+        );
+        assert_eq!(
+            Endpoint::default().transcription_sessions_url().as_str(),
+            "https://api.openai.com/v1/realtime/transcription_sessions"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_azure_endpoint() {
+        let endpoint = Endpoint::azure("contoso", "gpt-4o-realtime", "2024-10-01-preview").unwrap();
+        assert!(Endpoint::azure("con toso/", "gpt-4o-realtime", "2024-10-01-preview").is_err());
+        assert_eq!(
+            endpoint.realtime_url(&Model::default()).as_str(),
+            "wss://contoso.openai.azure.com/openai/realtime?api-version=2024-10-01-preview&deployment=gpt-4o-realtime"
+        );
+        assert_eq!(
+            endpoint.sessions_url().as_str(),
+            "https://contoso.openai.azure.com/openai/realtimeapi/sessions?api-version=2024-10-01-preview"
+        );
+
+        let key = ApiKeyRef::Value("secret".to_string());
+        assert_eq!(
+            key.auth_header(&endpoint.provider).await.unwrap(),
+            ("api-key", "secret".to_string())
+        );
+        let entra_id = Provider::Azure {
+            deployment: "gpt-4o-realtime".to_string(),
+            api_version: "2024-10-01-preview".to_string(),
+            auth: AzureAuth::EntraId,
+        };
+        assert_eq!(
+            key.auth_header(&entra_id).await.unwrap(),
+            ("Authorization", "Bearer secret".to_string())
+        );
+
+        // a provider is asked every time
+        let calls = Arc::new(AtomicUsize::new(0));
+        let key = ApiKeyRef::Provider(TokenProvider::new({
+            let calls = calls.clone();
+            move || {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { Ok(format!("token-{n}")) }
+            }
+        }));
+        for n in 1..=2 {
+            assert_eq!(
+                key.auth_header(&entra_id).await.unwrap(),
+                ("Authorization", format!("Bearer token-{n}"))
+            );
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::api::item::{ContentPart, Item};
+use crate::api::server_event::ServerEvent;
+use serde::{Deserialize, Serialize};
+
+/// An item of the [`Conversation`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationItem {
+    /// The item as last reported by the server, with streamed text, transcripts and arguments
+    /// applied
+    pub item: Item,
+    /// Where the audio of the item was truncated, e.g. because the user interrupted it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_end_ms: Option<u32>,
+}
+
+impl ConversationItem {
+    pub fn id(&self) -> Option<&str> {
+        self.item.id()
+    }
+
+    /// Text or transcript of the content part at `content_index`
+    pub fn transcript(&self, content_index: usize) -> Option<&str> {
+        let Item::Message(message) = &self.item else {
+            return None;
+        };
+        match message.content.get(content_index)? {
+            ContentPart::InputText { text } | ContentPart::Text { text } => Some(text),
+            ContentPart::InputAudio { transcript, .. } | ContentPart::Audio { transcript, .. } => {
+                transcript.as_deref()
+            }
+            ContentPart::ItemReference { .. } => None,
+        }
+    }
+
+    /// Text and transcripts of all content parts, joined by newlines
+    pub fn text(&self) -> String {
+        let Item::Message(message) = &self.item else {
+            return String::new();
+        };
+        (0..message.content.len())
+            .filter_map(|i| self.transcript(i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn part_mut(&mut self, content_index: u32) -> Option<&mut ContentPart> {
+        match &mut self.item {
+            Item::Message(message) => message.content.get_mut(content_index as usize),
+            _ => None,
+        }
+    }
+
+    fn append_transcript(&mut self, content_index: u32, delta: &str) {
+        match self.part_mut(content_index) {
+            Some(ContentPart::Text { text }) => text.push_str(delta),
+            Some(
+                ContentPart::InputAudio { transcript, .. } | ContentPart::Audio { transcript, .. },
+            ) => transcript.get_or_insert_default().push_str(delta),
+            _ => {}
+        }
+    }
+
+    fn set_transcript(&mut self, content_index: u32, value: String) {
+        match self.part_mut(content_index) {
+            Some(ContentPart::Text { text }) => *text = value,
+            Some(
+                ContentPart::InputAudio { transcript, .. } | ContentPart::Audio { transcript, .. },
+            ) => *transcript = Some(value),
+            _ => {}
+        }
+    }
+}
+
+/// Client-side copy of the server's conversation, kept up to date by applying server events in
+/// the order they arrive.
+/// See: https://platform.openai.com/docs/guides/realtime-conversations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conversation {
+    items: Vec<ConversationItem>,
+}
+
+impl Conversation {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ConversationItem> {
+        self.items.iter()
+    }
+
+    pub fn get(&self, item_id: &str) -> Option<&ConversationItem> {
+        self.items.iter().find(|item| item.id() == Some(item_id))
+    }
+
+    /// Updates the conversation with a server event, other events are ignored
+    pub fn apply(&mut self, evt: &ServerEvent) {
+        match evt {
+            ServerEvent::ConversationItemCreated(evt) => {
+                self.insert(evt.item.clone(), evt.previous_item_id.as_deref());
+            }
+            ServerEvent::ConversationItemDeleted(evt) => {
+                self.items
+                    .retain(|item| item.id() != Some(evt.item_id.as_str()));
+            }
+            ServerEvent::ConversationItemTruncated(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.audio_end_ms = Some(evt.audio_end_ms);
+                    // the server drops the transcript, as it may contain unheard text
+                    if let Some(ContentPart::Audio { transcript, .. }) =
+                        item.part_mut(evt.content_index)
+                    {
+                        *transcript = None;
+                    }
+                }
+            }
+            ServerEvent::ResponseOutputItemAdded(evt)
+                if evt.item.id().and_then(|id| self.get(id)).is_none() =>
+            {
+                self.items.push(ConversationItem {
+                    item: evt.item.clone(),
+                    audio_end_ms: None,
+                });
+            }
+            ServerEvent::ResponseOutputItemDone(evt) => {
+                match evt.item.id().and_then(|id| self.get_mut(id)) {
+                    Some(item) => item.item = evt.item.clone(),
+                    None => self.items.push(ConversationItem {
+                        item: evt.item.clone(),
+                        audio_end_ms: None,
+                    }),
+                }
+            }
+            ServerEvent::ResponseContentPartAdded(evt) => {
+                if let Some(ConversationItem {
+                    item: Item::Message(message),
+                    ..
+                }) = self.get_mut(&evt.item_id)
+                {
+                    let index = evt.content_index as usize;
+                    if index < message.content.len() {
+                        message.content[index] = evt.part.clone();
+                    } else {
+                        message.content.push(evt.part.clone());
+                    }
+                }
+            }
+            ServerEvent::ResponseTextDelta(evt)
+            | ServerEvent::ResponseAudioTranscriptDelta(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.append_transcript(evt.content_index, &evt.delta);
+                }
+            }
+            ServerEvent::ResponseTextDone(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.set_transcript(evt.content_index, evt.text.clone());
+                }
+            }
+            ServerEvent::ResponseAudioTranscriptDone(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.set_transcript(evt.content_index, evt.transcript.clone());
+                }
+            }
+            ServerEvent::InputAudioTranscriptionDelta(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.append_transcript(evt.content_index, &evt.delta);
+                }
+            }
+            ServerEvent::InputAudioTranscriptionCompleted(evt) => {
+                if let Some(item) = self.get_mut(&evt.item_id) {
+                    item.set_transcript(evt.content_index, evt.transcript.clone());
+                }
+            }
+            ServerEvent::ResponseFunctionCallArgumentsDelta(evt) => {
+                if let Some(ConversationItem {
+                    item: Item::FunctionCall(call),
+                    ..
+                }) = self.get_mut(&evt.item_id)
+                {
+                    call.arguments.push_str(&evt.delta);
+                }
+            }
+            ServerEvent::ResponseFunctionCallArgumentsDone(evt) => {
+                if let Some(ConversationItem {
+                    item: Item::FunctionCall(call),
+                    ..
+                }) = self.get_mut(&evt.item_id)
+                {
+                    call.arguments = evt.arguments.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Clears the conversation, e.g. because the server-side session is new
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn get_mut(&mut self, item_id: &str) -> Option<&mut ConversationItem> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id() == Some(item_id))
+    }
+
+    /// Inserts `item` after `previous_item_id`, at the start if it has no predecessor. An item
+    /// that is already known, e.g. from `response.output_item.added`, is moved.
+    fn insert(&mut self, item: Item, previous_item_id: Option<&str>) {
+        let known = item
+            .id()
+            .and_then(|id| self.items.iter().position(|i| i.id() == Some(id)))
+            .map(|pos| self.items.remove(pos));
+        let item = ConversationItem {
+            item,
+            audio_end_ms: known.and_then(|known| known.audio_end_ms),
+        };
+        let pos = match previous_item_id {
+            None => 0,
+            Some(previous) => self
+                .items
+                .iter()
+                .position(|i| i.id() == Some(previous))
+                .map_or(self.items.len(), |pos| pos + 1),
+        };
+        self.items.insert(pos, item);
+    }
+}
+
+impl<'a> IntoIterator for &'a Conversation {
+    type Item = &'a ConversationItem;
+    type IntoIter = std::slice::Iter<'a, ConversationItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::item::Item;
+    use crate::api::server_event::ServerEvent;
+    use crate::conversation::Conversation;
+    use serde_json::{Value, json};
+
+    fn apply(conversation: &mut Conversation, events: Vec<Value>) {
+        for (i, mut evt) in events.into_iter().enumerate() {
+            evt["event_id"] = json!(format!("event_{i}"));
+            let evt: ServerEvent = serde_json::from_value(evt).unwrap();
+            conversation.apply(&evt);
+        }
+    }
+
+    fn message(id: &str, role: &str, content: Value) -> Value {
+        json!({ "id": id, "object": "realtime.item", "type": "message", "role": role, "content": content })
+    }
+
+    #[test]
+    fn test_apply_events() {
+        let mut conversation = Conversation::default();
+        let audio_delta = |delta: &str| {
+            json!({
+                "type": "response.audio_transcript.delta", "response_id": "resp_1",
+                "item_id": "msg_2", "output_index": 0, "content_index": 0, "delta": delta
+            })
+        };
+        apply(
+            &mut conversation,
+            vec![
+                json!({
+                    "type": "conversation.item.created", "previous_item_id": null,
+                    "item": message("msg_1", "user", json!([{ "type": "input_audio", "transcript": null }]))
+                }),
+                json!({
+                    "type": "response.output_item.added", "response_id": "resp_1", "output_index": 0,
+                    "item": message("msg_2", "assistant", json!([]))
+                }),
+                json!({
+                    "type": "conversation.item.created", "previous_item_id": "msg_1",
+                    "item": message("msg_2", "assistant", json!([]))
+                }),
+                json!({
+                    "type": "response.content_part.added", "response_id": "resp_1", "item_id": "msg_2",
+                    "output_index": 0, "content_index": 0, "part": { "type": "audio", "transcript": "" }
+                }),
+                audio_delta("Hello, "),
+                audio_delta("how can I help?"),
+                json!({
+                    "type": "conversation.item.input_audio_transcription.completed",
+                    "item_id": "msg_1", "content_index": 0, "transcript": "Hi"
+                }),
+                // inserted at the start, before `msg_1`
+                json!({
+                    "type": "conversation.item.created", "previous_item_id": null,
+                    "item": message("msg_0", "system", json!([{ "type": "input_text", "text": "Be brief" }]))
+                }),
+            ],
+        );
+
+        let ids: Vec<_> = conversation.iter().filter_map(|i| i.id()).collect();
+        assert_eq!(ids, ["msg_0", "msg_1", "msg_2"]);
+        assert_eq!(conversation.get("msg_1").unwrap().text(), "Hi");
+        assert_eq!(
+            conversation.get("msg_2").unwrap().transcript(0),
+            Some("Hello, how can I help?")
+        );
+
+        apply(
+            &mut conversation,
+            vec![
+                json!({
+                    "type": "conversation.item.truncated", "item_id": "msg_2",
+                    "content_index": 0, "audio_end_ms": 1500
+                }),
+                json!({ "type": "conversation.item.deleted", "item_id": "msg_0" }),
+                // unknown item types are kept in order
+                json!({
+                    "type": "conversation.item.created", "previous_item_id": "msg_2",
+                    "item": { "id": "mcp_1", "type": "mcp_list_tools", "tools": [] }
+                }),
+            ],
+        );
+        let truncated = conversation.get("msg_2").unwrap();
+        assert_eq!(truncated.audio_end_ms, Some(1500));
+        assert_eq!(truncated.transcript(0), None);
+        assert!(matches!(truncated.item, Item::Message(_)));
+        let ids: Vec<_> = conversation.iter().filter_map(|i| i.id()).collect();
+        assert_eq!(ids, ["msg_1", "msg_2", "mcp_1"]);
+        assert_eq!(conversation.get("mcp_1").unwrap().text(), "");
+
+        apply(
+            &mut conversation,
+            vec![json!({ "type": "conversation.item.deleted", "item_id": "mcp_1" })],
+        );
+        assert_eq!(conversation.len(), 2);
+
+        // snapshots can be persisted
+        let restored: Conversation =
+            serde_json::from_value(serde_json::to_value(&conversation).unwrap()).unwrap();
+        assert_eq!(restored.len(), 2);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::api::model::Model;
+use crate::api::response::Usage;
+use std::collections::HashMap;
+
+/// Prices in USD per million tokens
+/// See: https://platform.openai.com/docs/pricing
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenPrices {
+    pub text_input: f64,
+    pub cached_text_input: f64,
+    pub text_output: f64,
+    pub audio_input: f64,
+    pub cached_audio_input: f64,
+    pub audio_output: f64,
+}
+
+impl TokenPrices {
+    /// Cost of `usage` in USD
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let input = &usage.input_token_details;
+        let cached = &input.cached_tokens_details;
+        let output = &usage.output_token_details;
+        // cached tokens are part of the text and audio tokens
+        let tokens = [
+            (
+                input.text_tokens.saturating_sub(cached.text_tokens),
+                self.text_input,
+            ),
+            (cached.text_tokens, self.cached_text_input),
+            (
+                input.audio_tokens.saturating_sub(cached.audio_tokens),
+                self.audio_input,
+            ),
+            (cached.audio_tokens, self.cached_audio_input),
+            (output.text_tokens, self.text_output),
+            (output.audio_tokens, self.audio_output),
+        ];
+        tokens
+            .iter()
+            .map(|(tokens, price)| *tokens as f64 * price)
+            .sum::<f64>()
+            / 1_000_000.0
+    }
+}
+
+/// Token prices by model. The default table holds the list prices of the realtime models at the
+/// time of writing; set current or negotiated prices with [`set`](Self::set).
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: HashMap<Model, TokenPrices>,
+}
+
+impl PriceTable {
+    /// A table without prices
+    pub fn empty() -> Self {
+        Self {
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Sets the prices of `model`, also used for its dated snapshots without own prices
+    pub fn set(&mut self, model: Model, prices: TokenPrices) -> &mut Self {
+        self.prices.insert(model, prices);
+        self
+    }
+
+    /// Prices of `model`, or of the model a dated snapshot was taken of, e.g.
+    /// `gpt-4o-realtime-preview` for `gpt-4o-realtime-preview-2025-06-03`
+    pub fn get(&self, model: &Model) -> Option<&TokenPrices> {
+        self.prices.get(model).or_else(|| {
+            let base = snapshot_of(&model.0)?;
+            self.prices.get(&Model(base.to_string()))
+        })
+    }
+
+    /// Cost of `usage` in USD, `None` if the model has no prices
+    pub fn cost(&self, model: &Model, usage: &Usage) -> Option<f64> {
+        self.get(model).map(|prices| prices.cost(usage))
+    }
+}
+
+/// The model name in front of a `-YYYY-MM-DD` snapshot date
+fn snapshot_of(name: &str) -> Option<&str> {
+    let (base, date) = name.split_at_checked(name.len().checked_sub(11)?)?;
+    let date = date.strip_prefix('-')?.as_bytes();
+    let is_date = date.iter().enumerate().all(|(i, b)| match i {
+        4 | 7 => *b == b'-',
+        _ => b.is_ascii_digit(),
+    });
+    is_date.then_some(base)
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let gpt_4o = TokenPrices {
+            text_input: 5.0,
+            cached_text_input: 2.5,
+            text_output: 20.0,
+            audio_input: 40.0,
+            cached_audio_input: 2.5,
+            audio_output: 80.0,
+        };
+        let mut table = Self::empty();
+        table
+            .set(Model("gpt-4o-realtime-preview".to_string()), gpt_4o)
+            .set(
+                Model("gpt-4o-realtime-preview-2024-10-01".to_string()),
+                TokenPrices {
+                    audio_input: 100.0,
+                    cached_audio_input: 20.0,
+                    audio_output: 200.0,
+                    ..gpt_4o
+                },
+            )
+            .set(
+                Model("gpt-4o-mini-realtime-preview".to_string()),
+                TokenPrices {
+                    text_input: 0.6,
+                    cached_text_input: 0.3,
+                    text_output: 2.4,
+                    audio_input: 10.0,
+                    cached_audio_input: 0.3,
+                    audio_output: 20.0,
+                },
+            )
+            .set(
+                Model("gpt-realtime-mini".to_string()),
+                TokenPrices {
+                    text_input: 0.6,
+                    cached_text_input: 0.06,
+                    text_output: 2.4,
+                    audio_input: 10.0,
+                    cached_audio_input: 0.3,
+                    audio_output: 20.0,
+                },
+            )
+            .set(
+                Model("gpt-realtime".to_string()),
+                TokenPrices {
+                    text_input: 4.0,
+                    cached_text_input: 0.4,
+                    text_output: 16.0,
+                    audio_input: 32.0,
+                    cached_audio_input: 0.4,
+                    audio_output: 64.0,
+                },
+            );
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::model::Model;
+    use crate::api::response::{CachedTokenDetails, InputTokenDetails, OutputTokenDetails, Usage};
+    use crate::cost::{PriceTable, TokenPrices};
+
+    #[test]
+    fn test_cost() {
+        let usage = Usage {
+            total_tokens: 3_000_000,
+            input_tokens: 2_000_000,
+            output_tokens: 1_000_000,
+            input_token_details: InputTokenDetails {
+                text_tokens: 1_000_000,
+                audio_tokens: 1_000_000,
+                cached_tokens: 500_000,
+                cached_tokens_details: CachedTokenDetails {
+                    text_tokens: 500_000,
+                    audio_tokens: 0,
+                },
+            },
+            output_token_details: OutputTokenDetails {
+                text_tokens: 0,
+                audio_tokens: 1_000_000,
+            },
+        };
+
+        // 0.5M text at 5, 0.5M cached text at 2.5, 1M audio at 40, 1M audio out at 80
+        let table = PriceTable::default();
+        let cost = table.cost(&Model::default(), &usage).unwrap();
+        assert!((cost - 123.75).abs() < 1e-9, "{cost}");
+
+        // dated snapshots fall back to their model's prices
+        let snapshot = Model("gpt-4o-mini-realtime-preview-2024-12-17".to_string());
+        assert_eq!(table.get(&snapshot).unwrap().audio_output, 20.0);
+        assert!(table.get(&Model("whisper-1".to_string())).is_none());
+
+        // other models sharing a prefix are not snapshots
+        let mini = table.get(&Model("gpt-realtime-mini".to_string())).unwrap();
+        assert_eq!(mini.audio_output, 20.0);
+        let mini_snapshot = Model("gpt-realtime-mini-2025-10-06".to_string());
+        assert_eq!(table.get(&mini_snapshot), Some(mini));
+        assert_eq!(
+            table
+                .get(&Model("gpt-realtime-2025-08-28".to_string()))
+                .unwrap()
+                .audio_output,
+            64.0
+        );
+        assert!(table.get(&Model("gpt-realtime-nano".to_string())).is_none());
+
+        let mut table = PriceTable::empty();
+        table.set(
+            Model("gpt-4o-realtime-preview".to_string()),
+            TokenPrices {
+                audio_output: 1.0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(table.cost(&Model::default(), &usage), Some(1.0));
+    }
+}
@@ -1,6 +1,172 @@
+use crate::api::server_event::ErrorDetails;
+use std::fmt::Display;
+
 #[derive(Debug)]
 pub enum RealtimeError {
+    /// A client event could not be encoded
     Serialization(serde_json::Error),
+    /// An HTTP request failed before a response was received
     Http(reqwest::Error),
+    /// The HTTP API answered with an error status
+    Api { status: u16, message: String },
+    /// The websocket connection could not be established or failed
     Websocket(ezsockets::Error),
+    /// A message from the server could not be decoded
+    Decode { reason: String, payload: String },
+    /// The server reported an `error` event
+    /// See: https://platform.openai.com/docs/api-reference/realtime-server-events/error
+    Server(ErrorDetails),
+    /// The API key or token was rejected
+    Auth(ErrorDetails),
+    /// A rate limit was exceeded
+    RateLimited(ErrorDetails),
+    /// The connection is closed for good, nothing can be sent anymore
+    ConnectionClosed,
+    /// The connection dropped, the event was not sent or its outcome is unknown. Sending works
+    /// again once reconnected.
+    ConnectionLost,
+    /// The operation did not complete in time
+    Timeout,
+    /// The configuration cannot be used, e.g. the API key is missing
+    InvalidConfig(String),
+    /// The operation conflicts with the session's state, e.g. function calls are already taken
+    InvalidState(String),
+}
+
+impl Display for RealtimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RealtimeError::Serialization(e) => write!(f, "failed to encode event: {e}"),
+            RealtimeError::Http(e) => write!(f, "http request failed: {e}"),
+            RealtimeError::Api { status, message } => write!(f, "api error {status}: {message}"),
+            RealtimeError::Websocket(e) => write!(f, "websocket error: {e}"),
+            RealtimeError::Decode { reason, payload } => {
+                write!(f, "failed to decode server message: {reason}: {payload}")
+            }
+            RealtimeError::Server(details) => {
+                write!(f, "server error ({}", details.error_type)?;
+                if let Some(code) = &details.code {
+                    write!(f, ", code: {code}")?;
+                }
+                if let Some(param) = &details.param {
+                    write!(f, ", param: {param}")?;
+                }
+                if let Some(event_id) = &details.event_id {
+                    write!(f, ", event_id: {event_id}")?;
+                }
+                write!(f, "): {}", details.message)
+            }
+            RealtimeError::Auth(details) => write!(f, "authentication failed: {}", details.message),
+            RealtimeError::RateLimited(details) => write!(f, "rate limited: {}", details.message),
+            RealtimeError::ConnectionClosed => write!(f, "connection closed"),
+            RealtimeError::ConnectionLost => write!(f, "connection lost"),
+            RealtimeError::Timeout => write!(f, "timed out"),
+            RealtimeError::InvalidConfig(message) => write!(f, "invalid config: {message}"),
+            RealtimeError::InvalidState(message) => write!(f, "invalid state: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RealtimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RealtimeError::Serialization(e) => Some(e),
+            RealtimeError::Http(e) => Some(e),
+            RealtimeError::Websocket(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorDetails> for RealtimeError {
+    /// Classifies an `error` event by its code
+    fn from(details: ErrorDetails) -> Self {
+        match details.code.as_deref() {
+            Some("rate_limit_exceeded") => RealtimeError::RateLimited(details),
+            Some("invalid_api_key" | "invalid_authentication") => RealtimeError::Auth(details),
+            _ => RealtimeError::Server(details),
+        }
+    }
+}
+
+impl RealtimeError {
+    /// Maps an HTTP error status and its body, usually `{"error": {...}}` with the fields of
+    /// [`ErrorDetails`]
+    pub(crate) fn from_status(status: reqwest::StatusCode, body: String) -> Self {
+        let error = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .map(|mut v| v["error"].take());
+        let field = |name: &str| {
+            error
+                .as_ref()
+                .and_then(|error| error[name].as_str())
+                .map(str::to_string)
+        };
+        let details = ErrorDetails {
+            error_type: field("type").unwrap_or_else(|| "http_error".to_string()),
+            code: field("code"),
+            message: field("message").unwrap_or(body),
+            param: field("param"),
+            event_id: None,
+        };
+        match status.as_u16() {
+            401 | 403 => RealtimeError::Auth(details),
+            429 => RealtimeError::RateLimited(details),
+            status => RealtimeError::Api {
+                status,
+                message: details.message,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::server_event::ErrorDetails;
+    use crate::error::RealtimeError;
+
+    #[test]
+    fn test_classify_errors() {
+        let details = |code: &str| ErrorDetails {
+            error_type: "invalid_request_error".to_string(),
+            code: Some(code.to_string()),
+            message: "Invalid value: 'nova'".to_string(),
+            param: Some("session.voice".to_string()),
+            event_id: Some("evt_1".to_string()),
+        };
+
+        let err = RealtimeError::from(details("invalid_value"));
+        assert!(matches!(err, RealtimeError::Server(_)));
+        assert_eq!(
+            err.to_string(),
+            "server error (invalid_request_error, code: invalid_value, param: session.voice, event_id: evt_1): Invalid value: 'nova'"
+        );
+        let RealtimeError::RateLimited(limited) =
+            RealtimeError::from(details("rate_limit_exceeded"))
+        else {
+            panic!("expected a rate limit error")
+        };
+        assert_eq!(limited.param.as_deref(), Some("session.voice"));
+        assert_eq!(limited.event_id.as_deref(), Some("evt_1"));
+
+        let err = RealtimeError::from_status(
+            reqwest::StatusCode::UNAUTHORIZED,
+            r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","param":null,"code":"invalid_api_key"}}"#.to_string(),
+        );
+        assert_eq!(
+            err.to_string(),
+            "authentication failed: Incorrect API key provided"
+        );
+        let RealtimeError::Auth(details) = err else {
+            unreachable!()
+        };
+        assert_eq!(details.code.as_deref(), Some("invalid_api_key"));
+
+        let err = RealtimeError::from_status(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            "Too Many Requests".to_string(),
+        );
+        assert!(matches!(err, RealtimeError::RateLimited(details)
+            if details.message == "Too Many Requests" && details.error_type == "http_error"));
+    }
 }
@@ -1,38 +1,122 @@
+use crate::api::client_event::ClientEvent;
+use crate::api::server_event::{ErrorDetails, LogProb, RateLimit, ServerEvent};
 use crate::api::session::Session;
+use crate::error::RealtimeError;
 use nanoid::nanoid;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 
+/// A client event as sent over the wire, tagged with a unique `event_id`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventMessage {
     pub event_id: String,
 
-    #[serde(rename = "type")]
-    pub event_type: String,
-
     #[serde(flatten)]
-    pub body: serde_json::Value,
+    pub event: ClientEvent,
 }
 
 impl EventMessage {
-    pub fn wrap(evt: &str, body: impl Serialize) -> Self {
+    pub fn new(event: ClientEvent) -> Self {
         Self {
             event_id: nanoid!(),
-            event_type: evt.to_string(),
-            body: serde_json::to_value(body).unwrap(),
+            event,
         }
     }
+}
+
+/// A completed function call requested by the model
+#[derive(Debug, Clone)]
+pub struct FunctionCall {
+    pub response_id: String,
+    pub item_id: String,
+    pub call_id: String,
+    pub name: String,
+    /// JSON-encoded arguments
+    pub arguments: String,
+}
 
-    pub fn to_string(&self) -> String {
-        serde_json::to_string(&self).unwrap()
+impl FunctionCall {
+    /// Decodes the arguments into `T`
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(&self.arguments)
     }
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Event {
     Audio(Vec<u8>),
     SessionCreated(Session),
     TranscriptDelta(String),
     TranscriptDone(String),
-    InputAudioBufferSpeechStarted,
-    AudioDone,
+    /// Server turn detection heard the user start speaking, `audio_start_ms` into the input
+    /// audio buffer. The speech becomes item `item_id` once it stops.
+    InputAudioBufferSpeechStarted {
+        item_id: String,
+        audio_start_ms: u32,
+    },
+    /// Part of the transcript of the user's audio in item `item_id`
+    InputTranscriptDelta {
+        item_id: String,
+        content_index: u32,
+        delta: String,
+        /// Log probabilities of the tokens, if requested with `include`
+        logprobs: Option<Vec<LogProb>>,
+    },
+    /// The complete transcript of the user's audio in item `item_id`
+    InputTranscriptDone {
+        item_id: String,
+        content_index: u32,
+        transcript: String,
+        logprobs: Option<Vec<LogProb>>,
+    },
+    /// The user's audio in item `item_id` could not be transcribed
+    InputTranscriptFailed {
+        item_id: String,
+        content_index: u32,
+        error: ErrorDetails,
+    },
+    /// An audio content part of the assistant starts, its audio follows
+    AudioStarted {
+        response_id: String,
+        item_id: String,
+        content_index: u32,
+    },
+    /// All audio of the content part has been sent
+    AudioDone {
+        response_id: String,
+        item_id: String,
+        content_index: u32,
+    },
+    /// The assistant was interrupted and its audio item truncated at `audio_end_ms`. Audio sinks
+    /// should drop what they still buffer.
+    Interrupted {
+        item_id: String,
+        content_index: u32,
+        audio_end_ms: u32,
+    },
+    FunctionCall(FunctionCall),
+    /// The connection dropped and attempt `attempt` to reconnect starts after `delay`
+    Reconnecting {
+        attempt: usize,
+        delay: Duration,
+    },
+    /// The connection was re-established. The server-side session is new, the session
+    /// configuration has been replayed.
+    Reconnected,
+    /// The connection is closed for good
+    Disconnected,
+    /// The remaining share of a rate limit dropped below the session's `rate_limit_threshold`
+    RateLimitLow(RateLimit),
+    /// The session's cost in USD exceeded the agent's budget
+    BudgetExceeded {
+        cost: f64,
+        budget: f64,
+    },
+    /// An `error` event of the server or a server message that could not be decoded
+    Error(Arc<RealtimeError>),
+    /// Any other server event
+    Server(ServerEvent),
 }
@@ -1,14 +1,49 @@
 mod agent;
 mod api;
+pub mod audio;
 mod config;
+mod conversation;
+mod cost;
 mod error;
 mod event;
+mod playback;
+mod ptt;
+mod rate_limit;
 mod session;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod tool;
+mod transcription;
 mod websocket;
 
 pub use agent::*;
-pub use api::{model::*, response::ResponseCreateEvent, session::*, voice::*};
-pub use config::ApiKeyRef;
+pub use api::{
+    client_event::ClientEvent,
+    item::*,
+    model::*,
+    response::{
+        CachedTokenDetails, InputTokenDetails, OutputTokenDetails, Response, ResponseCreateEvent,
+        ResponseError, ResponseStatus, ResponseStatusDetails, StatusReason, Usage,
+    },
+    server_event::*,
+    session::*,
+    voice::*,
+};
+pub use config::{ApiKeyRef, AzureAuth, Endpoint, Provider, TokenProvider};
+pub use conversation::{Conversation, ConversationItem};
+pub use cost::{PriceTable, TokenPrices};
 pub use error::RealtimeError;
-pub use session::{SessionConfig, create_ephemeral_token, create_session};
-pub use websocket::{RealtimeSession, config::WebsocketConfig, connect};
+pub use event::{Event, EventMessage, FunctionCall};
+pub use playback::{PlaybackPosition, PlaybackTracker};
+pub use ptt::{PushToTalk, Turn};
+pub use rate_limit::RateLimits;
+pub use session::{
+    SessionConfig, create_ephemeral_token, create_session, create_transcription_session,
+};
+pub use tool::{ToolHandler, ToolRegistry};
+pub use transcription::{Transcript, TranscriptionConfig, TranscriptionSession, Transcripts};
+pub use websocket::{
+    AudioReceiver, ConnectionState, EventHandle, RealtimeSession,
+    config::{OutputPadding, ReconnectPolicy, WebsocketConfig},
+    connect,
+};
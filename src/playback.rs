@@ -0,0 +1,202 @@
+use crate::api::session::AudioFormat;
+use std::collections::VecDeque;
+
+/// How far playback of an assistant audio content part got
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackPosition {
+    pub item_id: String,
+    pub content_index: u32,
+    /// Played audio of the content part in milliseconds
+    pub offset_ms: u32,
+}
+
+/// Audio handed to the sink, in the order it was handed out
+#[derive(Debug)]
+struct Segment {
+    /// `None` for audio not belonging to an item, e.g. padding
+    part: Option<(String, u32)>,
+    /// samples of the content part handed out before this segment
+    start: u64,
+    samples: u64,
+    played: u64,
+}
+
+/// Tracks which assistant audio has actually been played. Chunks are registered with
+/// [`push`](Self::push) as they are handed to the audio sink, and the sink acknowledges played
+/// samples with [`played`](Self::played).
+#[derive(Debug, Default)]
+pub struct PlaybackTracker {
+    format: AudioFormat,
+    segments: VecDeque<Segment>,
+    position: Option<(String, u32, u64)>,
+}
+
+impl PlaybackTracker {
+    pub fn new(format: AudioFormat) -> Self {
+        Self {
+            format,
+            ..Default::default()
+        }
+    }
+
+    pub fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    /// Sets the format of subsequent chunks, e.g. after the session's `output_audio_format` changed.
+    /// Audio already tracked is rescaled to the new sample rate, keeping its duration.
+    pub fn set_format(&mut self, format: AudioFormat) {
+        let (from, to) = (
+            self.format.sample_rate() as u64,
+            format.sample_rate() as u64,
+        );
+        self.format = format;
+        if from == to {
+            return;
+        }
+        let rescale = |samples: &mut u64| *samples = *samples * to / from;
+        for segment in &mut self.segments {
+            rescale(&mut segment.start);
+            rescale(&mut segment.samples);
+            rescale(&mut segment.played);
+        }
+        if let Some((_, _, offset)) = &mut self.position {
+            rescale(offset);
+        }
+    }
+
+    /// Registers `bytes` of audio handed to the sink. `part` is the item id and content index the
+    /// audio belongs to.
+    pub fn push(&mut self, part: Option<(&str, u32)>, bytes: usize) {
+        let samples = (bytes / self.format.bytes_per_sample()) as u64;
+        if let Some(last) = self.segments.back_mut()
+            && last.part.as_ref().map(|(id, index)| (id.as_str(), *index)) == part
+        {
+            last.samples += samples;
+            return;
+        }
+        let start = match (part, &self.position) {
+            // the part continues after its already played audio
+            (Some((item_id, content_index)), Some((id, index, offset)))
+                if self.segments.is_empty() && id == item_id && *index == content_index =>
+            {
+                *offset
+            }
+            _ => 0,
+        };
+        self.segments.push_back(Segment {
+            part: part.map(|(id, index)| (id.to_string(), index)),
+            start,
+            samples,
+            played: 0,
+        });
+    }
+
+    /// Acknowledges `samples` more samples as played by the sink
+    pub fn played(&mut self, mut samples: u64) {
+        while samples > 0 {
+            let Some(segment) = self.segments.front_mut() else {
+                return;
+            };
+            let n = samples.min(segment.samples - segment.played);
+            segment.played += n;
+            samples -= n;
+            if let Some((item_id, content_index)) = &segment.part {
+                self.position = Some((
+                    item_id.clone(),
+                    *content_index,
+                    segment.start + segment.played,
+                ));
+            }
+            if segment.played == segment.samples {
+                self.segments.pop_front();
+            }
+        }
+    }
+
+    /// The content part played last and how much of it was played
+    pub fn position(&self) -> Option<PlaybackPosition> {
+        self.position
+            .as_ref()
+            .map(|(item_id, content_index, samples)| PlaybackPosition {
+                item_id: item_id.clone(),
+                content_index: *content_index,
+                offset_ms: self.format.samples_to_ms(*samples),
+            })
+    }
+
+    /// Handed out audio that has not been played yet, in milliseconds
+    pub fn queued_ms(&self) -> u32 {
+        let samples = self.segments.iter().map(|s| s.samples - s.played).sum();
+        self.format.samples_to_ms(samples)
+    }
+
+    /// Forgets the audio that has not been played, e.g. because the sink dropped it
+    pub fn flush(&mut self) {
+        self.segments.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::session::AudioFormat;
+    use crate::playback::{PlaybackPosition, PlaybackTracker};
+
+    #[test]
+    fn test_track_playback() {
+        let mut tracker = PlaybackTracker::new(AudioFormat::PCM16);
+        assert_eq!(tracker.position(), None);
+
+        // 100ms of item_1, 50ms padding, 100ms of item_2
+        tracker.push(Some(("item_1", 0)), 2400);
+        tracker.push(Some(("item_1", 0)), 2400);
+        tracker.push(None, 2400);
+        tracker.push(Some(("item_2", 0)), 4800);
+        assert_eq!(tracker.queued_ms(), 250);
+
+        tracker.played(1200);
+        assert_eq!(
+            tracker.position(),
+            Some(PlaybackPosition {
+                item_id: "item_1".to_string(),
+                content_index: 0,
+                offset_ms: 50,
+            })
+        );
+
+        // through the padding into item_2
+        tracker.played(1200 + 1200 + 480);
+        let position = tracker.position().unwrap();
+        assert_eq!(position.item_id, "item_2");
+        assert_eq!(position.offset_ms, 20);
+
+        // more of item_2 arrives after the queue ran dry
+        tracker.played(10_000);
+        assert_eq!(tracker.queued_ms(), 0);
+        tracker.push(Some(("item_2", 0)), 480);
+        tracker.played(240);
+        assert_eq!(tracker.position().unwrap().offset_ms, 110);
+
+        tracker.flush();
+        assert_eq!(tracker.queued_ms(), 0);
+        assert_eq!(tracker.position().unwrap().offset_ms, 110);
+    }
+
+    #[test]
+    fn test_format_change() {
+        let mut tracker = PlaybackTracker::new(AudioFormat::PCM16);
+        // 100ms of item_1, 50ms of it played
+        tracker.push(Some(("item_1", 0)), 4800);
+        tracker.played(1200);
+
+        tracker.set_format(AudioFormat::G711Ulaw);
+        assert_eq!(tracker.queued_ms(), 50);
+        assert_eq!(tracker.position().unwrap().offset_ms, 50);
+
+        // 8kHz samples from now on
+        tracker.played(200);
+        assert_eq!(tracker.position().unwrap().offset_ms, 75);
+        tracker.push(Some(("item_1", 0)), 800);
+        assert_eq!(tracker.queued_ms(), 125);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::api::response::ResponseCreateEvent;
+use crate::api::server_event::ServerEvent;
+use crate::api::session::{AudioFormat, SessionUpdateEvent, TurnDetection};
+use crate::error::RealtimeError;
+use crate::event::Event;
+use crate::websocket::{EventHandle, RealtimeSession};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// The server rejects commits of less audio
+const MIN_COMMIT_MS: u32 = 100;
+
+/// Error code of a commit with too little audio in the buffer
+const COMMIT_EMPTY: &str = "input_audio_buffer_commit_empty";
+
+/// How long [`PushToTalk::release`] waits for the commit to be confirmed
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a push-to-talk turn ended, see [`PushToTalk::release`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Turn {
+    /// The audio was committed as item `item_id` and a response requested
+    Committed { item_id: String },
+    /// Too little audio to commit, e.g. the button was only tapped. Nothing was sent.
+    Empty,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pressed: bool,
+    /// Audio appended since the press
+    appended_ms: u32,
+}
+
+/// Push-to-talk on top of a [`RealtimeSession`]: the user's turn is the audio appended while
+/// the button is held, and releasing it asks for the response. Server turn detection is turned
+/// off while the controller is active.
+/// See: https://platform.openai.com/docs/guides/realtime-conversations#voice-activity-detection
+pub struct PushToTalk {
+    session: Arc<RealtimeSession>,
+    state: Mutex<State>,
+}
+
+impl PushToTalk {
+    /// Turns off turn detection of `session` and takes over its turns
+    pub fn activate(session: Arc<RealtimeSession>) -> Result<Self, RealtimeError> {
+        session.session_update(SessionUpdateEvent {
+            turn_detection: Some(TurnDetection::None),
+            ..Default::default()
+        })?;
+        Ok(Self {
+            session,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Starts a turn: interrupts the assistant and drops audio left in the input buffer
+    pub fn press(&self) -> Result<(), RealtimeError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.pressed {
+                return Ok(());
+            }
+            *state = State {
+                pressed: true,
+                appended_ms: 0,
+            };
+        }
+        debug!("push-to-talk> pressed");
+        self.session.interrupt()?;
+        self.session.input_audio_buffer_clear()?;
+        Ok(())
+    }
+
+    /// Whether the button is held
+    pub fn is_pressed(&self) -> bool {
+        self.state.lock().unwrap().pressed
+    }
+
+    /// Appends audio bytes, encoded in the session's `input_audio_format`. Audio is dropped
+    /// while the button is not held, so a microphone can stream continuously.
+    pub fn audio_append(&self, buffer: Vec<u8>) -> Result<Option<EventHandle>, RealtimeError> {
+        self.audio_append_from(self.session.input_audio_format(), buffer)
+    }
+
+    /// Appends audio encoded in `format`, see [`audio_append`](Self::audio_append)
+    pub fn audio_append_from(
+        &self,
+        format: AudioFormat,
+        buffer: Vec<u8>,
+    ) -> Result<Option<EventHandle>, RealtimeError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if !state.pressed {
+                return Ok(None);
+            }
+            state.appended_ms += format.bytes_to_ms(buffer.len());
+        }
+        self.session.audio_append_from(format, buffer).map(Some)
+    }
+
+    /// Ends the turn: commits the input audio buffer and, once the server confirmed the commit,
+    /// requests a response
+    pub async fn release(&self) -> Result<Turn, RealtimeError> {
+        let appended_ms = {
+            let mut state = self.state.lock().unwrap();
+            if !state.pressed {
+                return Ok(Turn::Empty);
+            }
+            state.pressed = false;
+            state.appended_ms
+        };
+        if appended_ms < MIN_COMMIT_MS {
+            debug!("push-to-talk> released after {}ms, discarded", appended_ms);
+            self.session.input_audio_buffer_clear()?;
+            return Ok(Turn::Empty);
+        }
+
+        let mut events = self.session.subscribe();
+        let mut commit = self.session.input_audio_buffer_commit()?;
+        let committed = tokio::time::timeout(COMMIT_TIMEOUT, async {
+            loop {
+                tokio::select! {
+                    rejected = &mut commit => {
+                        return match rejected {
+                            Some(RealtimeError::Server(details))
+                                if details.code.as_deref() == Some(COMMIT_EMPTY) =>
+                            {
+                                warn!("push-to-talk> nothing to commit: {}", details.message);
+                                Ok(None)
+                            }
+                            Some(err) => Err(err),
+                            None => Err(RealtimeError::ConnectionClosed),
+                        };
+                    }
+                    evt = events.recv() => match evt {
+                        Ok(Event::Server(ServerEvent::InputAudioBufferCommitted(evt))) => {
+                            return Ok(Some(evt.item_id));
+                        }
+                        Ok(Event::Disconnected) | Err(RecvError::Closed) => {
+                            return Err(RealtimeError::ConnectionClosed);
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    },
+                }
+            }
+        })
+        .await
+        .map_err(|_| RealtimeError::Timeout)??;
+
+        let Some(item_id) = committed else {
+            return Ok(Turn::Empty);
+        };
+        debug!("push-to-talk> committed {} ({}ms)", item_id, appended_ms);
+        self.session
+            .response_create(ResponseCreateEvent::default())?;
+        Ok(Turn::Committed { item_id })
+    }
+
+    /// The controlled session
+    pub fn session(&self) -> &Arc<RealtimeSession> {
+        &self.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::client_event::ClientEvent;
+    use crate::api::session::{AudioFormat, TurnDetection};
+    use crate::ptt::{PushToTalk, Turn};
+    use crate::testing::MockServer;
+    use crate::websocket::connect;
+
+    #[tokio::test]
+    async fn test_push_to_talk() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let ptt = PushToTalk::activate(session.clone()).unwrap();
+        let (_, update) = mock
+            .wait_for(|evt| matches!(evt, ClientEvent::SessionUpdate { .. }))
+            .await
+            .unwrap();
+        let ClientEvent::SessionUpdate { session: update } = update else {
+            unreachable!()
+        };
+        assert_eq!(update.turn_detection, Some(TurnDetection::None));
+
+        // audio outside of a turn is dropped
+        assert!(ptt.audio_append(vec![0; 9_600]).unwrap().is_none());
+
+        // 200ms of PCM16
+        ptt.press().unwrap();
+        ptt.audio_append_from(AudioFormat::PCM16, vec![0; 9_600])
+            .unwrap()
+            .unwrap();
+        let Turn::Committed { item_id } = ptt.release().await.unwrap() else {
+            panic!("expected the turn to be committed")
+        };
+        mock.wait_for(|evt| matches!(evt, ClientEvent::ResponseCreate { .. }))
+            .await
+            .unwrap();
+        let received = mock.received();
+        let kinds: Vec<_> = received
+            .iter()
+            .filter(|evt| !matches!(evt, ClientEvent::SessionUpdate { .. }))
+            .map(|evt| match evt {
+                ClientEvent::InputAudioBufferClear => "clear",
+                ClientEvent::InputAudioBufferAppend { .. } => "append",
+                ClientEvent::InputAudioBufferCommit => "commit",
+                ClientEvent::ResponseCreate { .. } => "response",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["clear", "append", "commit", "response"]);
+        assert!(item_id.starts_with("item_"));
+
+        // a tap is not committed
+        ptt.press().unwrap();
+        assert_eq!(ptt.release().await.unwrap(), Turn::Empty);
+
+        // the server rejects the commit if the buffer is empty after all
+        ptt.press().unwrap();
+        ptt.audio_append(vec![0; 9_600]).unwrap();
+        session.input_audio_buffer_clear().unwrap();
+        assert_eq!(ptt.release().await.unwrap(), Turn::Empty);
+        assert!(!ptt.is_pressed());
+        let responses = mock
+            .received()
+            .iter()
+            .filter(|evt| matches!(evt, ClientEvent::ResponseCreate { .. }))
+            .count();
+        assert_eq!(responses, 1);
+    }
+}
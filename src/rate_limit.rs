@@ -0,0 +1,112 @@
+use crate::api::server_event::{RateLimit, RateLimitName};
+use std::time::{Duration, Instant};
+
+/// The rate limits last reported by the server with `rate_limits.updated`
+/// See: https://platform.openai.com/docs/guides/rate-limits
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    limits: Vec<RateLimit>,
+    updated_at: Option<Instant>,
+}
+
+impl RateLimits {
+    pub fn get(&self, name: &RateLimitName) -> Option<&RateLimit> {
+        self.limits.iter().find(|limit| &limit.name == name)
+    }
+
+    pub fn requests(&self) -> Option<&RateLimit> {
+        self.get(&RateLimitName::Requests)
+    }
+
+    pub fn tokens(&self) -> Option<&RateLimit> {
+        self.get(&RateLimitName::Tokens)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, RateLimit> {
+        self.limits.iter()
+    }
+
+    /// When the limits were received, `None` before the first update
+    pub fn updated_at(&self) -> Option<Instant> {
+        self.updated_at
+    }
+
+    /// Time left until the limit is reset
+    pub fn resets_in(&self, name: &RateLimitName) -> Option<Duration> {
+        let limit = self.get(name)?;
+        let reset = Duration::try_from_secs_f64(limit.reset_seconds).unwrap_or_default();
+        Some(reset.saturating_sub(self.updated_at?.elapsed()))
+    }
+
+    /// Replaces the limits and returns those whose remaining share dropped below `threshold`
+    /// with this update
+    pub fn update(&mut self, limits: Vec<RateLimit>, threshold: f64) -> Vec<RateLimit> {
+        let crossed = limits
+            .iter()
+            .filter(|limit| limit.remaining_ratio() < threshold)
+            .filter(|limit| {
+                self.get(&limit.name)
+                    .is_none_or(|previous| previous.remaining_ratio() >= threshold)
+            })
+            .cloned()
+            .collect();
+        self.limits = limits;
+        self.updated_at = Some(Instant::now());
+        crossed
+    }
+}
+
+impl<'a> IntoIterator for &'a RateLimits {
+    type Item = &'a RateLimit;
+    type IntoIter = std::slice::Iter<'a, RateLimit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.limits.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::server_event::{RateLimit, RateLimitName};
+    use crate::rate_limit::RateLimits;
+    use std::time::Duration;
+
+    fn limits(requests: u64, tokens: u64) -> Vec<RateLimit> {
+        vec![
+            RateLimit {
+                name: RateLimitName::Requests,
+                limit: 100,
+                remaining: requests,
+                reset_seconds: 60.0,
+            },
+            RateLimit {
+                name: RateLimitName::Tokens,
+                limit: 10_000,
+                remaining: tokens,
+                reset_seconds: 1.5,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_update() {
+        let mut rate_limits = RateLimits::default();
+        assert!(rate_limits.tokens().is_none());
+        assert!(rate_limits.update(limits(99, 9_000), 0.1).is_empty());
+        assert_eq!(rate_limits.requests().unwrap().remaining, 99);
+        assert!(
+            rate_limits.resets_in(&RateLimitName::Tokens).unwrap() <= Duration::from_millis(1500)
+        );
+
+        // reported once when crossing the threshold
+        let crossed = rate_limits.update(limits(98, 900), 0.1);
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0].name, RateLimitName::Tokens);
+        assert!(rate_limits.update(limits(97, 800), 0.1).is_empty());
+
+        // and again after recovering
+        assert!(rate_limits.update(limits(100, 10_000), 0.1).is_empty());
+        assert_eq!(rate_limits.update(limits(5, 500), 0.1).len(), 2);
+        assert_eq!(rate_limits.iter().count(), 2);
+    }
+}
@@ -1,13 +1,20 @@
 use crate::api::model::Model;
-use crate::api::session::{ClientSecret, CreateSessionRequest, Session};
+use crate::api::session::{
+    ClientSecret, CreateSessionRequest, Session, TranscriptionSessionResource,
+};
 use crate::api::voice::Voice;
-use crate::{ApiKeyRef, RealtimeError};
+use crate::transcription::TranscriptionConfig;
+use crate::{ApiKeyRef, Endpoint, RealtimeError};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use url::Url;
 
 #[derive(Debug, Clone)]
 pub struct SessionConfig {
     pub api_key_ref: ApiKeyRef,
     pub model: Model,
     pub voice: Voice,
+    pub endpoint: Endpoint,
 }
 
 impl Default for SessionConfig {
@@ -16,6 +23,7 @@ impl Default for SessionConfig {
             model: Model::default(),
             api_key_ref: ApiKeyRef::default(),
             voice: Voice::Verse,
+            endpoint: Endpoint::default(),
         }
     }
 }
@@ -24,41 +32,126 @@ impl Default for SessionConfig {
 /// See: https://platform.openai.com/docs/guides/realtime#connect-with-webrtc
 /// See: https://platform.openai.com/docs/guides/realtime#connection-details
 pub async fn create_ephemeral_token(config: &SessionConfig) -> Result<ClientSecret, RealtimeError> {
-    Ok(create_session(config).await?.client_secret.unwrap())
+    create_session(config)
+        .await?
+        .client_secret
+        .ok_or_else(|| RealtimeError::Decode {
+            reason: "missing client_secret".to_string(),
+            payload: String::new(),
+        })
 }
 
 /// Create a new Session
 pub async fn create_session(config: &SessionConfig) -> Result<Session, RealtimeError> {
+    post(
+        &config.endpoint,
+        &config.api_key_ref,
+        config.endpoint.sessions_url(),
+        &CreateSessionRequest {
+            model: config.endpoint.model(&config.model),
+            voice: config.voice.clone(),
+        },
+    )
+    .await
+}
+
+/// Creates a transcription session, e.g. for an ephemeral token of a client that only transcribes
+/// See: https://platform.openai.com/docs/api-reference/realtime-sessions/create-transcription
+pub async fn create_transcription_session(
+    config: &TranscriptionConfig,
+) -> Result<TranscriptionSessionResource, RealtimeError> {
+    post(
+        &config.endpoint,
+        &config.api_key_ref,
+        config.endpoint.transcription_sessions_url(),
+        &config.session_update(),
+    )
+    .await
+}
+
+async fn post<B: Serialize, R: DeserializeOwned>(
+    endpoint: &Endpoint,
+    api_key_ref: &ApiKeyRef,
+    url: Url,
+    body: &B,
+) -> Result<R, RealtimeError> {
     let client = reqwest::Client::new();
-    let response = client
-        .post("https://api.openai.com/v1/realtime/sessions")
-        .header(
-            "Authorization",
-            format!("Bearer {}", config.api_key_ref.clone().api_key()),
-        )
+    let mut request = client.post(url);
+    for (name, value) in &endpoint.headers {
+        request = request.header(name, value);
+    }
+    let (auth_name, auth_value) = api_key_ref.auth_header(&endpoint.provider).await?;
+    let response = request
+        .header(auth_name, auth_value)
         .header("Content-Type", "application/json")
-        .json(&CreateSessionRequest {
-            model: config.model.clone(),
-            voice: config.voice.clone(),
-        })
+        .json(body)
         .send()
         .await
-        .map_err(RealtimeError::Http)?
-        .json()
-        .await
         .map_err(RealtimeError::Http)?;
-    Ok(response)
+    let status = response.status();
+    let body = response.text().await.map_err(RealtimeError::Http)?;
+    if !status.is_success() {
+        return Err(RealtimeError::from_status(status, body));
+    }
+    serde_json::from_str(&body).map_err(|e| RealtimeError::Decode {
+        reason: e.to_string(),
+        payload: body,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::session::{SessionConfig, create_ephemeral_token};
+    use crate::config::{ApiKeyRef, AzureAuth, Provider};
+    use crate::error::RealtimeError;
+    use crate::session::create_ephemeral_token;
+    use crate::testing::MockServer;
 
     #[tokio::test]
     async fn test_get_token() {
-        let token = create_ephemeral_token(&SessionConfig::default())
+        let mock = MockServer::start().await;
+        let token = create_ephemeral_token(&mock.session_config())
             .await
             .unwrap();
-        println!("token: {token:?}");
+        assert!(token.value.starts_with("ek_"));
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.path, "/v1/realtime/sessions");
+        assert_eq!(
+            request.headers.get("authorization").unwrap(),
+            "Bearer test-key"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_token_azure() {
+        let mock = MockServer::start().await;
+        let mut config = mock.session_config();
+        config.endpoint.provider = Provider::Azure {
+            deployment: "my-realtime".to_string(),
+            api_version: "2025-04-01-preview".to_string(),
+            auth: AzureAuth::ApiKey,
+        };
+        create_ephemeral_token(&config).await.unwrap();
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.path, "/v1/realtimeapi/sessions");
+        assert_eq!(
+            request.query.get("api-version").unwrap(),
+            "2025-04-01-preview"
+        );
+        assert_eq!(request.headers.get("api-key").unwrap(), "test-key");
+        assert!(!request.headers.contains_key("authorization"));
+        assert!(request.body.contains("\"model\":\"my-realtime\""));
+    }
+
+    #[tokio::test]
+    async fn test_get_token_unauthorized() {
+        let mock = MockServer::start().await;
+        let mut config = mock.session_config();
+        config.api_key_ref = ApiKeyRef::Value("wrong-key".to_string());
+        let err = create_ephemeral_token(&config).await.unwrap_err();
+        assert!(
+            matches!(err, RealtimeError::Auth(details) if details.message == "Incorrect API key provided")
+        );
     }
 }
@@ -0,0 +1,620 @@
+//! A local mock of the Realtime API for offline tests.
+//!
+//! [`MockServer`] serves `POST /v1/realtime/sessions`, `POST /v1/realtime/transcription_sessions`
+//! and the `/v1/realtime` websocket on the same port, and accepts the Azure flavor of them. With
+//! `intent=transcription` the websocket acts as a transcription session. Connected clients get a
+//! `session.created` event and `session.update` is answered with `session.updated`, or with an
+//! `error` if the temperature is outside of 0.6..=1.2 like the real API does. The input audio
+//! buffer is tracked: `input_audio_buffer.commit` is answered with `input_audio_buffer.committed`,
+//! or with an `error` if it holds less than 100ms. Items created with `conversation.item.create`
+//! are confirmed and can be deleted with `conversation.item.delete`, unknown ids are rejected.
+//! Everything else is scripted by the test via
+//! [`MockServer::send`].
+//! Received client events are recorded and can be awaited with [`MockServer::wait_for`].
+//! Requests without the key [`MOCK_API_KEY`] are rejected with `401 Unauthorized`.
+
+use crate::api::client_event::ClientEvent;
+use crate::api::server_event::{
+    ConversationItemCreatedEvent, ConversationItemDeletedEvent, ErrorDetails, ErrorEvent,
+    InputAudioBufferClearedEvent, InputAudioBufferCommittedEvent, ServerEvent, SessionCreatedEvent,
+    SessionUpdatedEvent, TranscriptionSessionEvent,
+};
+use crate::api::session::Session;
+use crate::event::EventMessage;
+use crate::{ApiKeyRef, Endpoint, SessionConfig, WebsocketConfig};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use futures_util::{SinkExt, StreamExt};
+use nanoid::nanoid;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+/// The API key accepted by the [`MockServer`]
+pub const MOCK_API_KEY: &str = "test-key";
+
+/// An HTTP request or websocket handshake received by the [`MockServer`]
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// Header names are lowercase
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Default)]
+struct MockState {
+    session: Mutex<Option<Session>>,
+    requests: Mutex<Vec<MockRequest>>,
+    received: Mutex<Vec<EventMessage>>,
+    received_notify: Notify,
+    connections: Mutex<Vec<UnboundedSender<Message>>>,
+    tasks: Mutex<Vec<AbortHandle>>,
+}
+
+impl MockState {
+    fn session(&self) -> Session {
+        self.session
+            .lock()
+            .unwrap()
+            .get_or_insert_with(default_session)
+            .clone()
+    }
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(MockState::default());
+
+        let state_for_accept = state.clone();
+        let accept = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = state_for_accept.clone();
+                let task = tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, state).await {
+                        tracing::debug!("mock: connection failed: {e}");
+                    }
+                });
+                state_for_accept
+                    .tasks
+                    .lock()
+                    .unwrap()
+                    .push(task.abort_handle());
+            }
+        });
+        state.tasks.lock().unwrap().push(accept.abort_handle());
+
+        Self { addr, state }
+    }
+
+    /// Base URL of the mocked API, e.g. `http://127.0.0.1:4711/v1`
+    pub fn base_url(&self) -> Url {
+        Url::parse(&format!("http://{}/v1", self.addr)).unwrap()
+    }
+
+    /// An [`Endpoint`] pointing at this server
+    pub fn endpoint(&self) -> Endpoint {
+        Endpoint::new(self.base_url())
+    }
+
+    /// A [`WebsocketConfig`] pointing at this server
+    pub fn websocket_config(&self) -> WebsocketConfig {
+        WebsocketConfig {
+            endpoint: self.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// A [`SessionConfig`] pointing at this server
+    pub fn session_config(&self) -> SessionConfig {
+        SessionConfig {
+            endpoint: self.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the session returned by the HTTP API and sent with `session.created`
+    pub fn set_session(&self, session: Session) {
+        self.state.session.lock().unwrap().replace(session);
+    }
+
+    /// Current state of the mocked session
+    pub fn session(&self) -> Session {
+        self.state.session()
+    }
+
+    /// Sends a server event to all connected clients
+    pub fn send(&self, evt: ServerEvent) {
+        self.send_raw(serde_json::to_string(&evt).unwrap());
+    }
+
+    /// Sends an arbitrary text frame to all connected clients
+    pub fn send_raw(&self, text: impl Into<String>) {
+        let text = text.into();
+        self.state
+            .connections
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(Message::text(text.clone())).is_ok());
+    }
+
+    /// Closes all websocket connections
+    pub fn disconnect_all(&self) {
+        for tx in self.state.connections.lock().unwrap().drain(..) {
+            let _ = tx.send(Message::Close(None));
+        }
+    }
+
+    /// Number of currently open websocket connections
+    pub fn connections(&self) -> usize {
+        let mut connections = self.state.connections.lock().unwrap();
+        connections.retain(|tx| !tx.is_closed());
+        connections.len()
+    }
+
+    /// HTTP requests and websocket handshakes received so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Client events received so far
+    pub fn received(&self) -> Vec<ClientEvent> {
+        self.state
+            .received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|msg| msg.event.clone())
+            .collect()
+    }
+
+    /// Waits up to 5 seconds for a client event matching `f`, including already received ones.
+    /// Returns the `event_id` and the event.
+    pub async fn wait_for(
+        &self,
+        f: impl Fn(&ClientEvent) -> bool,
+    ) -> Option<(String, ClientEvent)> {
+        let wait = async {
+            loop {
+                let notified = self.state.received_notify.notified();
+                if let Some(msg) = self
+                    .state
+                    .received
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|msg| f(&msg.event))
+                {
+                    return (msg.event_id.clone(), msg.event.clone());
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .ok()
+    }
+
+    /// Waits up to 5 seconds until `n` websocket clients are connected
+    pub async fn wait_for_connections(&self, n: usize) -> bool {
+        let wait = async {
+            while self.connections() < n {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .is_ok()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        for task in self.state.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    state: Arc<MockState>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // peek the request head, so the websocket handshake can still consume it
+    let mut buf = vec![0; 16 * 1024];
+    let head_len = loop {
+        let n = stream.peek(&mut buf).await?;
+        if let Some(pos) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if n == 0 || n == buf.len() {
+            return Err("invalid request head".into());
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    };
+    let mut request = parse_request_head(&String::from_utf8_lossy(&buf[..head_len]))?;
+    let authorized = request.headers.get("api-key").map(String::as_str) == Some(MOCK_API_KEY)
+        || request.headers.get("authorization") == Some(&format!("Bearer {MOCK_API_KEY}"));
+
+    if authorized
+        && request
+            .headers
+            .get("upgrade")
+            .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+    {
+        let transcription =
+            request.query.get("intent").map(String::as_str) == Some("transcription");
+        state.requests.lock().unwrap().push(request);
+        return handle_websocket(stream, state, transcription).await;
+    }
+
+    let mut stream = stream;
+    let mut head = vec![0; head_len];
+    stream.read_exact(&mut head).await?;
+    let content_length = request
+        .headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+    request.body = String::from_utf8_lossy(&body).to_string();
+
+    let (status, body) = match (request.method.as_str(), request.path.as_str()) {
+        _ if !authorized => (
+            "401 Unauthorized",
+            json!({
+                "error": {
+                    "message": "Incorrect API key provided", "type": "invalid_request_error",
+                    "param": null, "code": "invalid_api_key"
+                }
+            })
+            .to_string(),
+        ),
+        ("POST", path) if path.ends_with("/transcription_sessions") => {
+            let mut session = default_transcription_session();
+            if let (Some(session), Ok(Value::Object(update))) = (
+                session.as_object_mut(),
+                serde_json::from_str::<Value>(&request.body),
+            ) {
+                session.extend(update);
+            }
+            session["client_secret"] = json!({
+                "value": format!("ek_{}", nanoid!()),
+                "expires_at": 0,
+            });
+            ("200 OK", session.to_string())
+        }
+        ("POST", path) if path.ends_with("/sessions") => {
+            let mut session = serde_json::to_value(state.session())?;
+            session["client_secret"] = json!({
+                "value": format!("ek_{}", nanoid!()),
+                "expires_at": session["expires_at"],
+            });
+            ("200 OK", session.to_string())
+        }
+        _ => (
+            "404 Not Found",
+            json!({ "error": { "message": "not found" } }).to_string(),
+        ),
+    };
+    state.requests.lock().unwrap().push(request);
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn parse_request_head(head: &str) -> Result<MockRequest, Box<dyn std::error::Error + Send + Sync>> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().ok_or("missing method")?.to_string();
+    let target = request_line.next().ok_or("missing target")?;
+    let url = Url::parse(&format!("http://localhost{target}"))?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+        .collect();
+    Ok(MockRequest {
+        method,
+        path: url.path().to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body: String::new(),
+    })
+}
+
+async fn handle_websocket(
+    stream: TcpStream,
+    state: Arc<MockState>,
+    transcription: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut sink, mut stream) = ws.split();
+
+    let (tx, mut rx) = unbounded_channel::<Message>();
+    state.connections.lock().unwrap().push(tx.clone());
+
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let close = matches!(msg, Message::Close(_));
+            if sink.send(msg).await.is_err() || close {
+                break;
+            }
+        }
+    });
+
+    let reply = |evt: ServerEvent| {
+        let _ = tx.send(Message::text(serde_json::to_string(&evt).unwrap()));
+    };
+
+    // bytes in the input audio buffer, the last item and the ids of all items
+    let mut buffered = 0;
+    let mut previous_item_id = None;
+    let mut items = vec![];
+    let mut transcription_session = default_transcription_session();
+    if transcription {
+        reply(ServerEvent::TranscriptionSessionCreated(
+            TranscriptionSessionEvent {
+                event_id: nanoid!(),
+                session: serde_json::from_value(transcription_session.clone())?,
+            },
+        ));
+    } else {
+        reply(ServerEvent::SessionCreated(SessionCreatedEvent {
+            event_id: nanoid!(),
+            session: state.session(),
+        }));
+    }
+
+    while let Some(Ok(msg)) = stream.next().await {
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let msg: EventMessage = match serde_json::from_str(text.as_str()) {
+            Ok(msg) => msg,
+            Err(e) => {
+                reply(ServerEvent::Error(ErrorEvent {
+                    event_id: nanoid!(),
+                    error: ErrorDetails {
+                        error_type: "invalid_request_error".to_string(),
+                        code: Some("invalid_event".to_string()),
+                        message: e.to_string(),
+                        param: None,
+                        event_id: None,
+                    },
+                }));
+                continue;
+            }
+        };
+
+        if let ClientEvent::SessionUpdate { session: update } = &msg.event
+            && let Some(temperature) = update.temperature.filter(|t| !(0.6..=1.2).contains(t))
+        {
+            reply(ServerEvent::Error(ErrorEvent {
+                event_id: nanoid!(),
+                error: ErrorDetails {
+                    error_type: "invalid_request_error".to_string(),
+                    code: Some(
+                        if temperature < 0.6 {
+                            "decimal_below_min_value"
+                        } else {
+                            "decimal_above_max_value"
+                        }
+                        .to_string(),
+                    ),
+                    message: format!(
+                        "Invalid 'session.temperature': {temperature}. Expected a value between 0.6 and 1.2."
+                    ),
+                    param: Some("session.temperature".to_string()),
+                    event_id: Some(msg.event_id.clone()),
+                },
+            }));
+        } else if let ClientEvent::InputAudioBufferAppend { audio } = &msg.event {
+            buffered += BASE64_STANDARD.decode(audio).map_or(0, |audio| audio.len());
+        } else if let ClientEvent::InputAudioBufferClear = &msg.event {
+            buffered = 0;
+            reply(ServerEvent::InputAudioBufferCleared(
+                InputAudioBufferClearedEvent {
+                    event_id: nanoid!(),
+                },
+            ));
+        } else if let ClientEvent::InputAudioBufferCommit = &msg.event {
+            let buffered_ms = state.session().input_audio_format.bytes_to_ms(buffered);
+            if buffered_ms < 100 {
+                reply(ServerEvent::Error(ErrorEvent {
+                    event_id: nanoid!(),
+                    error: ErrorDetails {
+                        error_type: "invalid_request_error".to_string(),
+                        code: Some("input_audio_buffer_commit_empty".to_string()),
+                        message: format!(
+                            "Error committing input audio buffer: buffer too small. Expected at least 100ms of audio, but buffer only has {buffered_ms}.00ms of audio."
+                        ),
+                        param: None,
+                        event_id: Some(msg.event_id.clone()),
+                    },
+                }));
+            } else {
+                let item_id = format!("item_{}", nanoid!());
+                buffered = 0;
+                items.push(item_id.clone());
+                reply(ServerEvent::InputAudioBufferCommitted(
+                    InputAudioBufferCommittedEvent {
+                        event_id: nanoid!(),
+                        previous_item_id: previous_item_id.replace(item_id.clone()),
+                        item_id,
+                    },
+                ));
+            }
+        } else if let ClientEvent::ConversationItemCreate {
+            previous_item_id: previous,
+            item,
+        } = &msg.event
+        {
+            let mut item = item.clone();
+            let item_id = match item.id() {
+                Some(id) => id.to_string(),
+                None => format!("item_{}", nanoid!()),
+            };
+            item.set_id(item_id.clone());
+            items.push(item_id.clone());
+            let previous = match previous {
+                Some(previous) => Some(previous.clone()),
+                None => previous_item_id.replace(item_id),
+            };
+            reply(ServerEvent::ConversationItemCreated(
+                ConversationItemCreatedEvent {
+                    event_id: nanoid!(),
+                    previous_item_id: previous,
+                    item,
+                },
+            ));
+        } else if let ClientEvent::ConversationItemDelete { item_id } = &msg.event {
+            if let Some(index) = items.iter().position(|id| id == item_id) {
+                items.remove(index);
+                if previous_item_id.as_ref() == Some(item_id) {
+                    previous_item_id = items.last().cloned();
+                }
+                reply(ServerEvent::ConversationItemDeleted(
+                    ConversationItemDeletedEvent {
+                        event_id: nanoid!(),
+                        item_id: item_id.clone(),
+                    },
+                ));
+            } else {
+                reply(ServerEvent::Error(ErrorEvent {
+                    event_id: nanoid!(),
+                    error: ErrorDetails {
+                        error_type: "invalid_request_error".to_string(),
+                        code: Some("item_not_found".to_string()),
+                        message: format!("Item with item_id not found: {item_id}"),
+                        param: None,
+                        event_id: Some(msg.event_id.clone()),
+                    },
+                }));
+            }
+        } else if let ClientEvent::TranscriptionSessionUpdate { session: update } = &msg.event {
+            if let (Some(session), Value::Object(update)) = (
+                transcription_session.as_object_mut(),
+                serde_json::to_value(update)?,
+            ) {
+                session.extend(update);
+            }
+            reply(ServerEvent::TranscriptionSessionUpdated(
+                TranscriptionSessionEvent {
+                    event_id: nanoid!(),
+                    session: serde_json::from_value(transcription_session.clone())?,
+                },
+            ));
+        } else if let ClientEvent::SessionUpdate { session: update } = &msg.event {
+            let mut session = serde_json::to_value(state.session())?;
+            if let (Some(session), Value::Object(update)) =
+                (session.as_object_mut(), serde_json::to_value(update)?)
+            {
+                session.extend(update);
+            }
+            match serde_json::from_value::<Session>(session) {
+                Ok(session) => {
+                    state.session.lock().unwrap().replace(session.clone());
+                    reply(ServerEvent::SessionUpdated(SessionUpdatedEvent {
+                        event_id: nanoid!(),
+                        session,
+                    }));
+                }
+                Err(e) => reply(ServerEvent::Error(ErrorEvent {
+                    event_id: nanoid!(),
+                    error: ErrorDetails {
+                        error_type: "invalid_request_error".to_string(),
+                        code: Some("invalid_value".to_string()),
+                        message: e.to_string(),
+                        param: None,
+                        event_id: Some(msg.event_id.clone()),
+                    },
+                })),
+            }
+        }
+
+        state.received.lock().unwrap().push(msg);
+        state.received_notify.notify_waiters();
+    }
+
+    writer.abort();
+    Ok(())
+}
+
+fn default_transcription_session() -> Value {
+    json!({
+        "id": format!("sess_{}", nanoid!()),
+        "object": "realtime.transcription_session",
+        "expires_at": 0,
+        "input_audio_format": "pcm16",
+        "input_audio_transcription": { "model": "gpt-4o-transcribe", "language": null, "prompt": "" },
+        "input_audio_noise_reduction": null,
+        "turn_detection": {
+            "type": "server_vad",
+            "threshold": 0.5,
+            "prefix_padding_ms": 300,
+            "silence_duration_ms": 500
+        },
+        "include": null
+    })
+}
+
+fn default_session() -> Session {
+    serde_json::from_value(json!({
+        "id": format!("sess_{}", nanoid!()),
+        "object": "realtime.session",
+        "expires_at": 0,
+        "input_audio_noise_reduction": null,
+        "turn_detection": {
+            "type": "server_vad",
+            "threshold": 0.5,
+            "prefix_padding_ms": 300,
+            "silence_duration_ms": 200,
+            "create_response": true,
+            "interrupt_response": true
+        },
+        "input_audio_format": "pcm16",
+        "input_audio_transcription": null,
+        "include": null,
+        "model": "gpt-4o-realtime-preview-2024-12-17",
+        "modalities": ["text", "audio"],
+        "instructions": "",
+        "voice": "alloy",
+        "output_audio_format": "pcm16",
+        "tool_choice": "auto",
+        "temperature": 0.8,
+        "max_response_output_tokens": "inf",
+        "speed": 1.0,
+        "tracing": null,
+        "tools": []
+    }))
+    .unwrap()
+}
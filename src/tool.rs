@@ -0,0 +1,231 @@
+use crate::api::session::{FunctionTool, Tool};
+use crate::event::FunctionCall;
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Handles calls of a single function tool
+#[async_trait]
+pub trait ToolHandler: Send + Sync {
+    /// Invokes the tool with the JSON-encoded `arguments` sent by the model.
+    /// An `Err` is reported back to the model as `{"error": "..."}`.
+    async fn call(&self, arguments: &str) -> Result<Value, String>;
+}
+
+struct FnToolHandler<A, F> {
+    f: F,
+    _args: PhantomData<fn(A)>,
+}
+
+#[async_trait]
+impl<A, F, Fut, R, E> ToolHandler for FnToolHandler<A, F>
+where
+    A: DeserializeOwned + Send + 'static,
+    F: Fn(A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, E>> + Send + 'static,
+    R: Serialize,
+    E: Display,
+{
+    async fn call(&self, arguments: &str) -> Result<Value, String> {
+        let args: A =
+            serde_json::from_str(arguments).map_err(|e| format!("invalid arguments: {e}"))?;
+        let result = (self.f)(args).await.map_err(|e| e.to_string())?;
+        serde_json::to_value(result).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone)]
+struct RegisteredTool {
+    tool: FunctionTool,
+    handler: Arc<dyn ToolHandler>,
+}
+
+/// Function tools offered to the model together with their handlers
+#[derive(Clone)]
+pub struct ToolRegistry {
+    /// Maximum time a single handler may take before the call is reported as failed
+    pub timeout: Duration,
+    tools: BTreeMap<String, RegisteredTool>,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            tools: BTreeMap::new(),
+        }
+    }
+}
+
+impl Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("timeout", &self.timeout)
+            .field("tools", &self.tools.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ToolRegistry {
+    /// Registers a closure as tool. The JSON schema of the parameters is derived from `A`.
+    pub fn register<A, F, Fut, R, E>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        f: F,
+    ) where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send + 'static,
+        R: Serialize,
+        E: Display,
+    {
+        let mut parameters: Value = schemars::schema_for!(A).into();
+        if let Some(schema) = parameters.as_object_mut() {
+            schema.remove("$schema");
+            schema.remove("title");
+        }
+        self.register_handler(
+            name,
+            description,
+            parameters,
+            Arc::new(FnToolHandler {
+                f,
+                _args: PhantomData,
+            }),
+        );
+    }
+
+    /// Registers a handler with an explicit JSON schema for its parameters
+    pub fn register_handler(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+        handler: Arc<dyn ToolHandler>,
+    ) {
+        let name = name.into();
+        self.tools.insert(
+            name.clone(),
+            RegisteredTool {
+                tool: FunctionTool {
+                    name,
+                    description: description.into(),
+                    parameters,
+                },
+                handler,
+            },
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Tool definitions to announce in `session.update`
+    pub fn tools(&self) -> Vec<Tool> {
+        self.tools
+            .values()
+            .map(|t| Tool::Function(t.tool.clone()))
+            .collect()
+    }
+
+    /// Runs the handler for `call` and returns the output to send back to the model
+    pub async fn dispatch(&self, call: &FunctionCall) -> String {
+        let Some(tool) = self.tools.get(&call.name) else {
+            return json!({ "error": format!("unknown tool: {}", call.name) }).to_string();
+        };
+        match tokio::time::timeout(self.timeout, tool.handler.call(&call.arguments)).await {
+            Ok(Ok(output)) => output.to_string(),
+            Ok(Err(e)) => json!({ "error": e }).to_string(),
+            Err(_) => json!({
+                "error": format!("tool timed out after {}ms", self.timeout.as_millis())
+            })
+            .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::session::Tool;
+    use crate::event::FunctionCall;
+    use crate::tool::ToolRegistry;
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+    use std::time::Duration;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct WeatherArgs {
+        city: String,
+    }
+
+    fn call(name: &str, arguments: &str) -> FunctionCall {
+        FunctionCall {
+            response_id: "resp_1".into(),
+            item_id: "item_1".into(),
+            call_id: "call_1".into(),
+            name: name.into(),
+            arguments: arguments.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let mut registry = ToolRegistry {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+        registry.register(
+            "get_weather",
+            "Weather of a city",
+            |args: WeatherArgs| async move {
+                if args.city == "Atlantis" {
+                    return Err(format!("{} does not exist", args.city));
+                }
+                Ok(json!({ "city": args.city, "celsius": 21 }))
+            },
+        );
+        registry.register("sleep", "Sleeps", |_: WeatherArgs| async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, String>("done")
+        });
+
+        let Tool::Function(tool) = &registry.tools()[0];
+        assert_eq!(tool.name, "get_weather");
+        assert_eq!(tool.parameters["properties"]["city"]["type"], "string");
+
+        let output = registry
+            .dispatch(&call("get_weather", r#"{"city":"Berlin"}"#))
+            .await;
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({ "city": "Berlin", "celsius": 21 })
+        );
+
+        for (name, arguments, error) in [
+            (
+                "get_weather",
+                r#"{"city":"Atlantis"}"#,
+                "Atlantis does not exist",
+            ),
+            ("get_weather", "{}", "invalid arguments"),
+            ("sleep", r#"{"city":"Berlin"}"#, "timed out"),
+            ("unknown", "{}", "unknown tool"),
+        ] {
+            let output: Value =
+                serde_json::from_str(&registry.dispatch(&call(name, arguments)).await).unwrap();
+            let message = output["error"].as_str().unwrap();
+            assert!(message.contains(error), "{message}");
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::api::server_event::{ErrorDetails, LogProb};
+use crate::api::session::{
+    AudioFormat, InputAudioTranscription, NoiseReduction, TranscriptionSessionUpdateEvent,
+    TurnDetection,
+};
+use crate::config::{ApiKeyRef, Endpoint};
+use crate::error::RealtimeError;
+use crate::event::Event;
+use crate::websocket::config::{ReconnectPolicy, WebsocketConfig};
+use crate::websocket::{ConnectionState, EventHandle, RealtimeSession, connect_to};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch};
+use tracing::warn;
+
+/// Include value that adds log probabilities to the transcripts
+const LOGPROBS: &str = "item.input_audio_transcription.logprobs";
+
+/// Configuration of a [`TranscriptionSession`]
+#[derive(Debug, Clone)]
+pub struct TranscriptionConfig {
+    pub api_key_ref: ApiKeyRef,
+    pub endpoint: Endpoint,
+    pub input_audio_format: AudioFormat,
+    /// Transcription model, language and prompt
+    pub transcription: InputAudioTranscription,
+    /// `None` keeps the server's default
+    pub noise_reduction: Option<NoiseReduction>,
+    /// `None` keeps the server's default, server VAD. Transcription sessions never create
+    /// responses, `create_response` and `interrupt_response` are not sent.
+    pub turn_detection: Option<TurnDetection>,
+    /// Report log probabilities of the transcribed tokens
+    pub include_logprobs: bool,
+    pub reconnect: ReconnectPolicy,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            api_key_ref: ApiKeyRef::default(),
+            endpoint: Endpoint::default(),
+            input_audio_format: AudioFormat::default(),
+            transcription: InputAudioTranscription::new("gpt-4o-transcribe"),
+            noise_reduction: None,
+            turn_detection: None,
+            include_logprobs: false,
+            reconnect: ReconnectPolicy::default(),
+        }
+    }
+}
+
+impl TranscriptionConfig {
+    /// The session configuration sent with `transcription_session.update`
+    pub fn session_update(&self) -> TranscriptionSessionUpdateEvent {
+        TranscriptionSessionUpdateEvent {
+            input_audio_format: Some(self.input_audio_format),
+            input_audio_transcription: Some(self.transcription.clone()),
+            input_audio_noise_reduction: self.noise_reduction,
+            turn_detection: self
+                .turn_detection
+                .clone()
+                .map(TurnDetection::for_transcription),
+            include: self.include_logprobs.then(|| vec![LOGPROBS.to_string()]),
+        }
+    }
+}
+
+/// A transcript of the audio, see [`TranscriptionSession::transcripts`]
+#[derive(Debug, Clone)]
+pub enum Transcript {
+    /// Text added to the transcript of item `item_id`
+    Partial {
+        item_id: String,
+        content_index: u32,
+        delta: String,
+        logprobs: Option<Vec<LogProb>>,
+    },
+    /// The complete transcript of item `item_id`, replaces the partial text
+    Final {
+        item_id: String,
+        content_index: u32,
+        transcript: String,
+        logprobs: Option<Vec<LogProb>>,
+    },
+    /// The audio of item `item_id` could not be transcribed
+    Failed {
+        item_id: String,
+        content_index: u32,
+        error: ErrorDetails,
+    },
+}
+
+/// A session that only transcribes the audio appended to it, without generating responses,
+/// e.g. for live captions. Each turn detected in the audio becomes an item with a transcript.
+/// See: https://platform.openai.com/docs/guides/realtime-transcription
+pub struct TranscriptionSession {
+    session: Arc<RealtimeSession>,
+}
+
+impl TranscriptionSession {
+    pub async fn connect(config: TranscriptionConfig) -> Result<Self, RealtimeError> {
+        let ws_config = WebsocketConfig {
+            api_key_ref: config.api_key_ref.clone(),
+            endpoint: config.endpoint.clone(),
+            reconnect: config.reconnect.clone(),
+            barge_in: false,
+            ..Default::default()
+        };
+        let url = ws_config.endpoint.transcription_url();
+        let (session, _rx_audio) = connect_to(url, ws_config).await?;
+        session.transcription_session_update(config.session_update())?;
+        Ok(Self { session })
+    }
+
+    /// Appends audio bytes, encoded in the session's `input_audio_format`
+    pub fn audio_append(&self, buffer: Vec<u8>) -> Result<EventHandle, RealtimeError> {
+        self.session.audio_append(buffer)
+    }
+
+    /// Appends audio encoded in `format`, transcoding it to the session's `input_audio_format`
+    pub fn audio_append_from(
+        &self,
+        format: AudioFormat,
+        buffer: Vec<u8>,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.session.audio_append_from(format, buffer)
+    }
+
+    /// Ends the current turn. Not needed with turn detection.
+    pub fn commit(&self) -> Result<EventHandle, RealtimeError> {
+        self.session.input_audio_buffer_commit()
+    }
+
+    /// Drops the audio of the current turn
+    pub fn clear(&self) -> Result<EventHandle, RealtimeError> {
+        self.session.input_audio_buffer_clear()
+    }
+
+    /// Replaces the session configuration
+    pub fn update(&self, config: &TranscriptionConfig) -> Result<EventHandle, RealtimeError> {
+        self.session
+            .transcription_session_update(config.session_update())
+    }
+
+    /// Partial and final transcripts, in the order they arrive
+    pub fn transcripts(&self) -> Transcripts {
+        Transcripts {
+            rx: self.session.subscribe(),
+        }
+    }
+
+    /// All events of the session, see [`RealtimeSession::subscribe`]
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.session.subscribe()
+    }
+
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.session.connection_state()
+    }
+
+    pub fn close(&self) {
+        self.session.close()
+    }
+
+    /// The underlying session, e.g. for an [`AudioInput`](crate::audio::pipeline::AudioInput)
+    pub fn realtime_session(&self) -> &Arc<RealtimeSession> {
+        &self.session
+    }
+}
+
+/// Receives the transcripts of a [`TranscriptionSession`]
+pub struct Transcripts {
+    rx: broadcast::Receiver<Event>,
+}
+
+impl Transcripts {
+    /// The next transcript, `None` once the session is closed
+    pub async fn recv(&mut self) -> Option<Transcript> {
+        loop {
+            let evt = match self.rx.recv().await {
+                Ok(evt) => evt,
+                Err(RecvError::Lagged(n)) => {
+                    warn!("transcripts lagged behind by {} events", n);
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            match evt {
+                Event::InputTranscriptDelta {
+                    item_id,
+                    content_index,
+                    delta,
+                    logprobs,
+                } => {
+                    return Some(Transcript::Partial {
+                        item_id,
+                        content_index,
+                        delta,
+                        logprobs,
+                    });
+                }
+                Event::InputTranscriptDone {
+                    item_id,
+                    content_index,
+                    transcript,
+                    logprobs,
+                } => {
+                    return Some(Transcript::Final {
+                        item_id,
+                        content_index,
+                        transcript,
+                        logprobs,
+                    });
+                }
+                Event::InputTranscriptFailed {
+                    item_id,
+                    content_index,
+                    error,
+                } => {
+                    return Some(Transcript::Failed {
+                        item_id,
+                        content_index,
+                        error,
+                    });
+                }
+                Event::Disconnected => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::client_event::ClientEvent;
+    use crate::api::session::{AudioFormat, Eagerness, InputAudioTranscription, TurnDetection};
+    use crate::config::ApiKeyRef;
+    use crate::session::create_transcription_session;
+    use crate::testing::{MOCK_API_KEY, MockServer};
+    use crate::transcription::{Transcript, TranscriptionConfig, TranscriptionSession};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_transcription_session() {
+        let mock = MockServer::start().await;
+        let config = TranscriptionConfig {
+            endpoint: mock.endpoint(),
+            api_key_ref: ApiKeyRef::Value(MOCK_API_KEY.to_string()),
+            input_audio_format: AudioFormat::G711Ulaw,
+            transcription: InputAudioTranscription {
+                language: Some("en".to_string()),
+                ..InputAudioTranscription::new("gpt-4o-mini-transcribe")
+            },
+            turn_detection: Some(TurnDetection::SemanticVad {
+                eagerness: Eagerness::Low,
+                create_response: Some(false),
+                interrupt_response: Some(false),
+            }),
+            include_logprobs: true,
+            ..Default::default()
+        };
+
+        // the response flags are neither sent nor part of the server's session
+        let resource = create_transcription_session(&config).await.unwrap();
+        assert!(resource.client_secret.unwrap().value.starts_with("ek_"));
+        assert_eq!(
+            resource.turn_detection,
+            TurnDetection::SemanticVad {
+                eagerness: Eagerness::Low,
+                create_response: None,
+                interrupt_response: None,
+            }
+        );
+        assert_eq!(
+            mock.requests()[0].path,
+            "/v1/realtime/transcription_sessions"
+        );
+
+        let session = TranscriptionSession::connect(config).await.unwrap();
+        assert_eq!(
+            mock.requests()[1].query.get("intent").unwrap(),
+            "transcription"
+        );
+        let (_, update) = mock
+            .wait_for(|evt| matches!(evt, ClientEvent::TranscriptionSessionUpdate { .. }))
+            .await
+            .unwrap();
+        let ClientEvent::TranscriptionSessionUpdate { session: update } = update else {
+            unreachable!()
+        };
+        assert_eq!(
+            update.input_audio_transcription.unwrap().model.as_deref(),
+            Some("gpt-4o-mini-transcribe")
+        );
+        assert_eq!(
+            update.include.unwrap(),
+            ["item.input_audio_transcription.logprobs"]
+        );
+        assert_eq!(
+            serde_json::to_value(update.turn_detection).unwrap(),
+            json!({ "type": "semantic_vad", "eagerness": "low" })
+        );
+
+        let mut transcripts = session.transcripts();
+        for evt in [
+            json!({
+                "type": "conversation.item.input_audio_transcription.delta", "event_id": "event_1",
+                "item_id": "item_1", "content_index": 0, "delta": "Hello",
+                "logprobs": [{ "token": "Hello", "logprob": -0.1, "bytes": [72, 101, 108, 108, 111] }]
+            }),
+            json!({
+                "type": "conversation.item.input_audio_transcription.completed", "event_id": "event_2",
+                "item_id": "item_1", "content_index": 0, "transcript": "Hello world"
+            }),
+        ] {
+            mock.send_raw(evt.to_string());
+        }
+        assert!(matches!(
+            transcripts.recv().await.unwrap(),
+            Transcript::Partial { delta, logprobs: Some(logprobs), .. }
+                if delta == "Hello" && logprobs.len() == 1
+        ));
+        assert!(matches!(
+            transcripts.recv().await.unwrap(),
+            Transcript::Final { item_id, transcript, .. }
+                if item_id == "item_1" && transcript == "Hello world"
+        ));
+
+        session.close();
+        assert!(transcripts.recv().await.is_none());
+    }
+}
@@ -1,27 +1,117 @@
-use crate::api::response::ResponseCreateEvent;
-use crate::api::session::{Session, SessionUpdateEvent};
+use crate::api::client_event::ClientEvent;
+use crate::api::item::{ContentPart, FunctionCallOutputItem, Item};
+use crate::api::model::Model;
+use crate::api::response::{ResponseCreateEvent, ResponseStatus, Usage};
+use crate::api::server_event::ServerEvent;
+use crate::api::session::{
+    AudioFormat, Session, SessionUpdateEvent, TranscriptionSessionUpdateEvent,
+};
+use crate::audio::codec::{self, Transcoder};
+use crate::config::{ApiKeyRef, Provider};
+use crate::conversation::Conversation;
+use crate::cost::PriceTable;
 use crate::error::RealtimeError;
-use crate::event::{Event, EventMessage};
-use crate::websocket::config::WebsocketConfig;
+use crate::event::{Event, EventMessage, FunctionCall};
+use crate::playback::{PlaybackPosition, PlaybackTracker};
+use crate::rate_limit::RateLimits;
+use crate::websocket::config::{OutputPadding, ReconnectPolicy, WebsocketConfig};
 use async_trait::async_trait;
-use ezsockets::{Error, Utf8Bytes};
+use base64::prelude::*;
+use ezsockets::client::{ClientCloseMode, ClientConnector};
+use ezsockets::{ClientConfig, ClientConnectorTokio, CloseFrame, Error, Utf8Bytes, WSError};
 use nanoid::nanoid;
-use serde::Serialize;
-use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
-use tokio::sync::{Mutex, oneshot};
-use tracing::{debug, error, info};
+use tokio::sync::{Mutex, broadcast, oneshot, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use url::Url;
 
 pub mod config {
-    use crate::ApiKeyRef;
     use crate::api::model::Model;
+    use crate::{ApiKeyRef, Endpoint};
+    use std::time::Duration;
     use url::Url;
 
+    /// Silence appended to the [`AudioReceiver`](super::AudioReceiver)'s audio after each audio
+    /// content part, e.g. for sinks that stop when running dry. Parts are delimited by
+    /// [`Event::AudioStarted`](crate::Event::AudioStarted) and
+    /// [`Event::AudioDone`](crate::Event::AudioDone) either way.
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub enum OutputPadding {
+        #[default]
+        None,
+        /// A fixed amount of silence
+        Fixed(Duration),
+        /// Silence in real time until the next response or audio content part starts, for at most
+        /// `max`
+        UntilNextResponse { max: Duration },
+    }
+
+    /// How to recover from a dropped connection
+    #[derive(Debug, Clone)]
+    pub struct ReconnectPolicy {
+        /// Consecutive attempts before giving up, `0` disables reconnecting
+        pub max_attempts: usize,
+        /// Delay before the first attempt, doubled for every further attempt
+        pub initial_backoff: Duration,
+        pub max_backoff: Duration,
+        /// Re-create the items sent with `conversation_item_create` after reconnecting
+        pub restore_history: bool,
+    }
+
+    impl Default for ReconnectPolicy {
+        fn default() -> Self {
+            Self {
+                max_attempts: 5,
+                initial_backoff: Duration::from_millis(500),
+                max_backoff: Duration::from_secs(10),
+                restore_history: false,
+            }
+        }
+    }
+
+    impl ReconnectPolicy {
+        /// Never reconnect
+        pub fn disabled() -> Self {
+            Self {
+                max_attempts: 0,
+                ..Default::default()
+            }
+        }
+
+        /// Delay before the given attempt, starting at 1
+        pub fn backoff(&self, attempt: usize) -> Duration {
+            let factor = 2u32.saturating_pow(attempt.saturating_sub(1) as u32);
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff)
+        }
+    }
+
     #[derive(Debug)]
     pub struct WebsocketConfig {
         pub model: Model,
         pub api_key_ref: ApiKeyRef,
+        pub endpoint: Endpoint,
+        pub reconnect: ReconnectPolicy,
+        /// Interrupt the assistant when the user starts speaking, see
+        /// [`RealtimeSession::interrupt`](super::RealtimeSession::interrupt). Off by default, set
+        /// it to `true` to enable it. The audio is truncated where playback got, so the played
+        /// audio has to be acknowledged with
+        /// [`RealtimeSession::audio_played`](super::RealtimeSession::audio_played).
+        pub barge_in: bool,
+        pub output_padding: OutputPadding,
+        /// Share of a rate limit below which
+        /// [`Event::RateLimitLow`](crate::Event::RateLimitLow) is sent
+        pub rate_limit_threshold: f64,
     }
 
     impl Default for WebsocketConfig {
@@ -29,47 +119,95 @@ pub mod config {
             Self {
                 model: Model::default(),
                 api_key_ref: ApiKeyRef::default(),
+                endpoint: Endpoint::default(),
+                reconnect: ReconnectPolicy::default(),
+                barge_in: false,
+                output_padding: OutputPadding::default(),
+                rate_limit_threshold: 0.1,
             }
         }
     }
 
     impl WebsocketConfig {
         pub fn url(&self) -> Url {
-            Url::parse(format!("wss://api.openai.com/v1/realtime?model={}", self.model).as_str())
-                .unwrap()
+            self.endpoint.realtime_url(&self.model)
         }
     }
 }
 
 pub async fn connect(
     config: WebsocketConfig,
-) -> Result<(Arc<RealtimeSession>, UnboundedReceiver<Vec<u8>>), RealtimeError> {
-    let ws_config = ezsockets::ClientConfig::new(config.url())
-        .bearer(config.api_key_ref.api_key())
-        .header("openai-beta", "realtime=v1");
+) -> Result<(Arc<RealtimeSession>, AudioReceiver), RealtimeError> {
+    connect_to(config.url(), config).await
+}
+
+/// Connects to `url` instead of the realtime URL of the configured model
+pub(crate) async fn connect_to(
+    url: Url,
+    config: WebsocketConfig,
+) -> Result<(Arc<RealtimeSession>, AudioReceiver), RealtimeError> {
+    // backoff is applied by the handle, see `WebsocketHandle::reconnect_or_close`
+    let mut ws_config = ezsockets::ClientConfig::new(url)
+        .header("openai-beta", "realtime=v1")
+        .reconnect_interval(Duration::ZERO);
+    for (name, value) in &config.endpoint.headers {
+        ws_config = ws_config.header(name.as_str(), value.as_str());
+    }
 
     let (tx_events, mut rx_events) = unbounded_channel();
     let (tx_connected, rx_connected) = oneshot::channel();
 
     let session_id = nanoid!(6);
+    let pending = PendingEvents::default();
+    let conversation = Arc::new(std::sync::Mutex::new(Conversation::default()));
+    let connection_state = Arc::new(watch::Sender::new(ConnectionState::Connected));
+    let session_updates = Arc::new(std::sync::Mutex::new(SessionUpdates::default()));
 
-    let (handle, _) = ezsockets::connect(
+    let connector = AuthConnector {
+        inner: ClientConnectorTokio::default(),
+        api_key_ref: config.api_key_ref.clone(),
+        provider: config.endpoint.provider.clone(),
+    };
+    let (handle, _) = ezsockets::connect_with(
         |handle| WebsocketHandle {
             _handle: handle,
             session_id: session_id.clone(),
             tx_events,
+            pending: pending.clone(),
+            conversation: conversation.clone(),
+            connection_state: connection_state.clone(),
+            session_updates: session_updates.clone(),
+            function_names: HashMap::new(),
             connected: Some(tx_connected),
+            reconnect: config.reconnect.clone(),
+            attempts: 0,
         },
         ws_config,
-    )
-    .await;
+        connector,
+    );
 
-    rx_connected.await.unwrap();
+    match rx_connected.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Err(RealtimeError::Websocket("connection aborted".into())),
+    }
 
     info!("connected");
 
     // create new realtime session
-    let (realtime_session, rx_audio) = RealtimeSession::new(session_id, Arc::new(handle));
+    let (realtime_session, rx_audio) = RealtimeSession::new(
+        session_id,
+        config.model,
+        Arc::new(handle),
+        config.reconnect,
+        config.barge_in,
+        config.output_padding,
+        config.rate_limit_threshold,
+        pending,
+        conversation,
+        connection_state,
+        session_updates,
+    );
 
     // process events
     let realtime_session_for_events = realtime_session.clone();
@@ -82,11 +220,127 @@ pub async fn connect(
     Ok((realtime_session, rx_audio))
 }
 
+/// Connects with the auth header resolved for every attempt, so reconnects present a fresh token
+struct AuthConnector {
+    inner: ClientConnectorTokio,
+    api_key_ref: ApiKeyRef,
+    provider: Provider,
+}
+
+#[async_trait]
+impl ClientConnector for AuthConnector {
+    type Handle = <ClientConnectorTokio as ClientConnector>::Handle;
+    type Message = <ClientConnectorTokio as ClientConnector>::Message;
+    type WSError = <ClientConnectorTokio as ClientConnector>::WSError;
+    type Socket = <ClientConnectorTokio as ClientConnector>::Socket;
+
+    fn handle(&self) -> Self::Handle {
+        self.inner.handle()
+    }
+
+    async fn connect(&self, config: &ClientConfig) -> Result<Self::Socket, Self::WSError> {
+        let (auth_name, auth_value) = self
+            .api_key_ref
+            .auth_header(&self.provider)
+            .await
+            .map_err(std::io::Error::other)?;
+        let mut request = ClientConfig::new(config.connect_url()).header(auth_name, auth_value);
+        for (name, value) in config.headers() {
+            request = request.header(name, value);
+        }
+        self.inner.connect(&request).await
+    }
+}
+
+/// Senders of the [`EventHandle`]s still waiting for a rejection, by `event_id`
+type PendingEvents = Arc<std::sync::Mutex<HashMap<String, oneshot::Sender<RealtimeError>>>>;
+
+/// The session configuration sent with `session.update`
+#[derive(Debug, Default)]
+struct SessionUpdates {
+    /// updates confirmed with `session.updated`, merged. Replayed after reconnecting.
+    confirmed: Option<SessionUpdateEvent>,
+    /// updates not answered yet by `event_id`, in the order they were sent
+    unconfirmed: VecDeque<(String, SessionUpdateEvent)>,
+}
+
+impl SessionUpdates {
+    /// The server answers updates in order, rejected ones are removed beforehand
+    fn confirm(&mut self) {
+        if let Some((_, update)) = self.unconfirmed.pop_front() {
+            self.confirmed.get_or_insert_default().merge(update);
+        }
+    }
+
+    fn reject(&mut self, event_id: &str) {
+        self.unconfirmed.retain(|(id, _)| id != event_id);
+    }
+}
+
 pub struct WebsocketHandle {
     _handle: ezsockets::Client<Self>,
     session_id: String,
     tx_events: UnboundedSender<Event>,
-    connected: Option<oneshot::Sender<()>>,
+    pending: PendingEvents,
+    conversation: Arc<std::sync::Mutex<Conversation>>,
+    connection_state: Arc<watch::Sender<ConnectionState>>,
+    session_updates: Arc<std::sync::Mutex<SessionUpdates>>,
+    /// function names by `call_id`, announced with `response.output_item.added`
+    function_names: HashMap<String, String>,
+    connected: Option<oneshot::Sender<Result<(), RealtimeError>>>,
+    reconnect: ReconnectPolicy,
+    /// consecutive reconnect attempts
+    attempts: usize,
+}
+
+impl WebsocketHandle {
+    fn emit(&self, evt: Event) {
+        if self.tx_events.send(evt).is_err() {
+            debug!(
+                "session({})> event dropped, session is gone",
+                self.session_id
+            );
+        }
+    }
+
+    /// Stops sending and fails the events still waiting for an answer, whether they were applied
+    /// is unknown
+    fn connection_lost(&self, state: ConnectionState) {
+        // `RealtimeSession::send` registers under the same lock
+        let mut pending = self.pending.lock().unwrap();
+        self.connection_state.send_replace(state);
+        for (_, tx) in pending.drain() {
+            let _ = tx.send(RealtimeError::ConnectionLost);
+        }
+        drop(pending);
+        // the new server-side session only gets the replayed configuration
+        self.session_updates.lock().unwrap().unconfirmed.clear();
+    }
+
+    async fn reconnect_or_close(&mut self) -> ClientCloseMode {
+        self.function_names.clear();
+        self.attempts += 1;
+        if self.attempts > self.reconnect.max_attempts {
+            info!("session({})> disconnected", self.session_id);
+            self.connection_lost(ConnectionState::Disconnected);
+            let _ = self.tx_events.send(Event::Disconnected);
+            return ClientCloseMode::Close;
+        }
+        self.connection_lost(ConnectionState::Reconnecting {
+            attempt: self.attempts,
+        });
+        let delay = self.reconnect.backoff(self.attempts);
+        info!(
+            "session({})> reconnecting in {:?} (attempt {}/{})",
+            self.session_id, delay, self.attempts, self.reconnect.max_attempts
+        );
+        let _ = self.tx_events.send(Event::Reconnecting {
+            attempt: self.attempts,
+            delay,
+        });
+        tokio::time::sleep(delay).await;
+        ClientCloseMode::Reconnect
+    }
 }
 
 #[async_trait]
@@ -94,108 +348,438 @@ impl ezsockets::ClientExt for WebsocketHandle {
     type Call = ();
 
     async fn on_text(&mut self, text: Utf8Bytes) -> Result<(), ezsockets::Error> {
-        let j: Value = serde_json::from_str(text.as_str()).unwrap();
-
-        let m = j.as_object().unwrap();
-        let event_type = m.get("type").unwrap().as_str().unwrap();
+        let evt: ServerEvent = match serde_json::from_str(text.as_str()) {
+            Ok(evt) => evt,
+            Err(e) => {
+                error!(
+                    "session({})> failed to decode event: {e}\n{}",
+                    self.session_id,
+                    text.as_str()
+                );
+                self.emit(Event::Error(Arc::new(RealtimeError::Decode {
+                    reason: e.to_string(),
+                    payload: text.to_string(),
+                })));
+                return Ok(());
+            }
+        };
 
-        if event_type.to_string() != "response.audio.delta" {
-            debug!(
-                "openai: received event: {event_type}\n{}",
-                serde_json::to_string_pretty(&j.clone()).unwrap()
+        if matches!(evt, ServerEvent::Unknown) {
+            warn!(
+                "session({})> unknown event: {}",
+                self.session_id,
+                text.as_str()
             );
+        } else if !matches!(evt, ServerEvent::ResponseAudioDelta(_)) {
+            debug!("session({})> event: {:?}", self.session_id, evt);
         }
+        self.conversation.lock().unwrap().apply(&evt);
 
-        debug!("session({})> event: {}", self.session_id, event_type);
-
-        match event_type {
-            "session.created" => {
-                self.tx_events
-                    .send(Event::SessionCreated(
-                        serde_json::from_value(m.get("session").unwrap().clone()).unwrap(),
-                    ))
-                    .unwrap();
+        match evt {
+            ServerEvent::Error(evt) => {
+                if let Some(event_id) = &evt.error.event_id {
+                    self.session_updates.lock().unwrap().reject(event_id);
+                }
+                let pending = evt
+                    .error
+                    .event_id
+                    .as_ref()
+                    .and_then(|id| self.pending.lock().unwrap().remove(id));
+                let err = RealtimeError::from(evt.error);
+                // errors nobody waits for go to the event stream
+                let uncorrelated = match pending {
+                    Some(tx) => tx.send(err).err(),
+                    None => Some(err),
+                };
+                if let Some(err) = uncorrelated {
+                    self.emit(Event::Error(Arc::new(err)));
+                }
             }
-            "response.audio.delta" => {
-                let decoded = base64::decode(m.get("delta").unwrap().as_str().unwrap()).unwrap();
-                self.tx_events.send(Event::Audio(decoded)).unwrap();
+            ServerEvent::SessionCreated(evt) => {
+                self.emit(Event::SessionCreated(evt.session));
             }
-            "response.audio_transcript.delta" => {
-                self.tx_events
-                    .send(Event::TranscriptDelta(
-                        serde_json::from_value(m.get("delta").unwrap().clone()).unwrap(),
-                    ))
-                    .unwrap();
+            ServerEvent::SessionUpdated(evt) => {
+                self.session_updates.lock().unwrap().confirm();
+                self.emit(Event::Server(ServerEvent::SessionUpdated(evt)));
             }
-            "response.audio_transcript.done" => {
-                self.tx_events
-                    .send(Event::TranscriptDone(
-                        serde_json::from_value(m.get("transcript").unwrap().clone()).unwrap(),
-                    ))
-                    .unwrap();
+            ServerEvent::ResponseAudioDelta(evt) => match BASE64_STANDARD.decode(&evt.delta) {
+                Ok(decoded) => self.emit(Event::Audio(decoded)),
+                Err(e) => self.emit(Event::Error(Arc::new(RealtimeError::Decode {
+                    reason: format!("invalid audio delta: {e}"),
+                    payload: evt.delta,
+                }))),
+            },
+            ServerEvent::ResponseAudioTranscriptDelta(evt) => {
+                self.emit(Event::TranscriptDelta(evt.delta));
             }
-            "input_audio_buffer.speech_started" => {
-                self.tx_events
-                    .send(Event::InputAudioBufferSpeechStarted)
-                    .unwrap();
+            ServerEvent::ResponseAudioTranscriptDone(evt) => {
+                self.emit(Event::TranscriptDone(evt.transcript));
             }
-            "response.audio.done" => {
-                println!("response.audio.done {:?}", m);
-
-                // TODO: when done, we should generate a bit of silence at then end
-
-                self.tx_events.send(Event::AudioDone).unwrap();
-
-                // TODO: figure out how much silence we actually need
-                let silence: Vec<u8> = vec![0; 48_000 * 2];
-                self.tx_events.send(Event::Audio(silence)).unwrap();
+            ServerEvent::InputAudioTranscriptionDelta(evt) => {
+                self.emit(Event::InputTranscriptDelta {
+                    item_id: evt.item_id,
+                    content_index: evt.content_index,
+                    delta: evt.delta,
+                    logprobs: evt.logprobs,
+                });
+            }
+            ServerEvent::InputAudioTranscriptionCompleted(evt) => {
+                self.emit(Event::InputTranscriptDone {
+                    item_id: evt.item_id,
+                    content_index: evt.content_index,
+                    transcript: evt.transcript,
+                    logprobs: evt.logprobs,
+                });
+            }
+            ServerEvent::InputAudioTranscriptionFailed(evt) => {
+                self.emit(Event::InputTranscriptFailed {
+                    item_id: evt.item_id,
+                    content_index: evt.content_index,
+                    error: evt.error,
+                });
+            }
+            ServerEvent::InputAudioBufferSpeechStarted(evt) => {
+                self.emit(Event::InputAudioBufferSpeechStarted {
+                    item_id: evt.item_id,
+                    audio_start_ms: evt.audio_start_ms,
+                });
+            }
+            ServerEvent::ResponseOutputItemAdded(evt) => {
+                if let Item::FunctionCall(call) = &evt.item {
+                    self.function_names
+                        .insert(call.call_id.clone(), call.name.clone());
+                }
+                self.emit(Event::Server(ServerEvent::ResponseOutputItemAdded(evt)));
+            }
+            ServerEvent::ResponseFunctionCallArgumentsDone(evt) => {
+                let known_name = self.function_names.remove(&evt.call_id);
+                let Some(name) = evt.name.clone().or(known_name) else {
+                    error!(
+                        "session({})> function call {} without name",
+                        self.session_id, evt.call_id
+                    );
+                    return Ok(());
+                };
+                self.emit(Event::FunctionCall(FunctionCall {
+                    response_id: evt.response_id.clone(),
+                    item_id: evt.item_id.clone(),
+                    call_id: evt.call_id.clone(),
+                    name,
+                    arguments: evt.arguments.clone(),
+                }));
+                self.emit(Event::Server(
+                    ServerEvent::ResponseFunctionCallArgumentsDone(evt),
+                ));
+            }
+            ServerEvent::ResponseContentPartAdded(evt)
+                if matches!(evt.part, ContentPart::Audio { .. }) =>
+            {
+                self.emit(Event::AudioStarted {
+                    response_id: evt.response_id.clone(),
+                    item_id: evt.item_id.clone(),
+                    content_index: evt.content_index,
+                });
+                self.emit(Event::Server(ServerEvent::ResponseContentPartAdded(evt)));
+            }
+            ServerEvent::ResponseAudioDone(evt) => {
+                self.emit(Event::AudioDone {
+                    response_id: evt.response_id,
+                    item_id: evt.item_id,
+                    content_index: evt.content_index,
+                });
+            }
+            evt => {
+                self.emit(Event::Server(evt));
             }
-            // TODO: response.audio.done
-            // TODO: response.audio_transcript.done
-            // TODO: response.done
-            _ => debug!(
-                "Unhandled event:\n{}",
-                serde_json::to_string_pretty(&j.clone()).unwrap()
-            ),
         }
 
-        //tracing::debug!("received message: {text}");
         Ok(())
     }
 
-    async fn on_binary(&mut self, _bytes: ezsockets::Bytes) -> Result<(), ezsockets::Error> {
-        unimplemented!()
+    async fn on_binary(&mut self, bytes: ezsockets::Bytes) -> Result<(), ezsockets::Error> {
+        debug!(
+            "session({})> ignoring {} bytes of binary data",
+            self.session_id,
+            bytes.len()
+        );
+        Ok(())
     }
 
-    async fn on_call(&mut self, call: Self::Call) -> Result<(), ezsockets::Error> {
+    async fn on_call(&mut self, _call: Self::Call) -> Result<(), ezsockets::Error> {
         Ok(())
     }
 
     async fn on_connect(&mut self) -> Result<(), Error> {
         if let Some(connected) = self.connected.take() {
-            connected.send(()).unwrap();
+            let _ = connected.send(Ok(()));
+        } else {
+            info!("session({})> reconnected", self.session_id);
+            self.attempts = 0;
+            // the new server-side session starts with an empty conversation
+            self.conversation.lock().unwrap().clear();
+            self.connection_state
+                .send_replace(ConnectionState::Connected);
+            let _ = self.tx_events.send(Event::Reconnected);
         }
         Ok(())
     }
+
+    async fn on_connect_fail(&mut self, e: WSError) -> Result<ClientCloseMode, Error> {
+        if let Some(connected) = self.connected.take() {
+            let err = match &e {
+                WSError::Http(response) => {
+                    let body = response
+                        .body()
+                        .as_deref()
+                        .map(String::from_utf8_lossy)
+                        .unwrap_or_default()
+                        .to_string();
+                    match reqwest::StatusCode::from_u16(response.status().as_u16()) {
+                        Ok(status) => RealtimeError::from_status(status, body),
+                        Err(_) => RealtimeError::Websocket(Box::new(e)),
+                    }
+                }
+                _ => RealtimeError::Websocket(Box::new(e)),
+            };
+            let _ = connected.send(Err(err));
+            return Ok(ClientCloseMode::Close);
+        }
+        error!("session({})> reconnect failed: {}", self.session_id, e);
+        Ok(self.reconnect_or_close().await)
+    }
+
+    async fn on_close(&mut self, _frame: Option<CloseFrame>) -> Result<ClientCloseMode, Error> {
+        Ok(self.reconnect_or_close().await)
+    }
+
+    async fn on_disconnect(&mut self) -> Result<ClientCloseMode, Error> {
+        Ok(self.reconnect_or_close().await)
+    }
+}
+
+/// State of the websocket connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting { attempt: usize },
+    Disconnected,
+}
+
+/// Handle of a client event sent with [`RealtimeSession::send`]. The server doesn't acknowledge
+/// accepted events, it only reports an `error` carrying the `event_id` of a rejected one.
+///
+/// Awaiting the handle resolves with the error once the event is rejected,
+/// [`RealtimeError::ConnectionLost`] if the connection drops first, or `None` when the session
+/// is closed. Dropping it sends rejections to the event stream instead.
+#[derive(Debug)]
+pub struct EventHandle {
+    event_id: String,
+    rx: oneshot::Receiver<RealtimeError>,
+}
+
+impl EventHandle {
+    pub fn event_id(&self) -> &str {
+        &self.event_id
+    }
+
+    /// Waits up to `timeout` for a rejection. `Ok` means no error was reported in time.
+    pub async fn check(self, timeout: Duration) -> Result<(), RealtimeError> {
+        match tokio::time::timeout(timeout, self).await {
+            Ok(Some(err)) => Err(err),
+            Ok(None) | Err(_) => Ok(()),
+        }
+    }
+}
+
+impl Future for EventHandle {
+    type Output = Option<RealtimeError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(Result::ok)
+    }
+}
+
+/// Receives the assistant's audio, in the session's `output_audio_format` unless another format
+/// is requested with [`set_format`](Self::set_format). Audio queued when the assistant is
+/// interrupted is dropped.
+#[derive(Debug)]
+pub struct AudioReceiver {
+    /// chunks by generation, an empty chunk ends an audio content part
+    rx: UnboundedReceiver<(u64, AudioFormat, Vec<u8>)>,
+    generation: Arc<AtomicU64>,
+    format: Arc<std::sync::Mutex<Option<AudioFormat>>>,
+    /// transcodes to the requested format within a generation
+    transcoder: Option<(u64, Transcoder)>,
+}
+
+impl AudioReceiver {
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let (generation, format, audio) = self.rx.recv().await?;
+            if let Some(audio) = self.deliver(generation, format, audio) {
+                return Some(audio);
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<Vec<u8>, TryRecvError> {
+        loop {
+            let (generation, format, audio) = self.rx.try_recv()?;
+            if let Some(audio) = self.deliver(generation, format, audio) {
+                return Ok(audio);
+            }
+        }
+    }
+
+    /// Receives the next chunk as sent by the server, along with its format. Ignores the format
+    /// requested with [`set_format`](Self::set_format).
+    pub async fn recv_with_format(&mut self) -> Option<(AudioFormat, Vec<u8>)> {
+        loop {
+            let (_, format, audio) = self.recv_chunk().await?;
+            if !audio.is_empty() {
+                return Some((format, audio));
+            }
+        }
+    }
+
+    /// Receives the next chunk of the current generation, including the empty chunks that end
+    /// each audio content part
+    pub(crate) async fn recv_chunk(&mut self) -> Option<(u64, AudioFormat, Vec<u8>)> {
+        loop {
+            let (generation, format, audio) = self.rx.recv().await?;
+            if generation == self.generation.load(Ordering::Acquire) {
+                return Some((generation, format, audio));
+            }
+        }
+    }
+
+    /// Delivers the audio transcoded to `format`, `None` delivers it as sent by the server.
+    /// Samples acknowledged with [`RealtimeSession::audio_played`] are counted in this format.
+    pub fn set_format(&mut self, format: Option<AudioFormat>) {
+        *self.format.lock().unwrap() = format;
+    }
+
+    /// Transcodes a chunk of the current generation to the requested format, `None` if there is
+    /// nothing to deliver
+    fn deliver(&mut self, generation: u64, format: AudioFormat, audio: Vec<u8>) -> Option<Vec<u8>> {
+        if generation != self.generation.load(Ordering::Acquire) {
+            return None;
+        }
+        let audio = match *self.format.lock().unwrap() {
+            Some(target) if target != format => {
+                // an interruption or another format starts over
+                let transcoder = match &mut self.transcoder {
+                    Some((g, transcoder))
+                        if *g == generation
+                            && transcoder.from_format() == format
+                            && transcoder.to_format() == target =>
+                    {
+                        transcoder
+                    }
+                    transcoder => {
+                        &mut transcoder
+                            .insert((generation, Transcoder::new(format, target)))
+                            .1
+                    }
+                };
+                if audio.is_empty() {
+                    transcoder.flush()
+                } else {
+                    transcoder.process(&audio)
+                }
+            }
+            _ => audio,
+        };
+        (!audio.is_empty()).then_some(audio)
+    }
+}
+
+/// The audio content part currently sent to the [`AudioReceiver`]
+#[derive(Debug)]
+struct OutputAudio {
+    item_id: String,
+    content_index: u32,
+    /// decoded bytes received from the server
+    received_bytes: usize,
+    /// playback position reported with `set_playback_position` or `audio_played`
+    played_ms: Option<u32>,
+    done: bool,
+    interrupted: bool,
+}
+
+impl OutputAudio {
+    fn received_ms(&self, format: AudioFormat) -> u32 {
+        format.bytes_to_ms(self.received_bytes)
+    }
 }
 
 pub struct RealtimeSession {
     id: String,
+    model: Model,
+    ws: Arc<ezsockets::Client<WebsocketHandle>>,
     session: Mutex<Option<Session>>,
-    tx_audio: UnboundedSender<Vec<u8>>,
+    tx_audio: UnboundedSender<(u64, AudioFormat, Vec<u8>)>,
+    audio_generation: Arc<AtomicU64>,
+    /// format requested by the `AudioReceiver`
+    delivery_format: Arc<std::sync::Mutex<Option<AudioFormat>>>,
+    /// the session's `input_audio_format`
+    input_format: std::sync::Mutex<AudioFormat>,
+    /// transcodes the audio appended with `audio_append_from`
+    input_transcoder: std::sync::Mutex<Option<Transcoder>>,
+    barge_in: bool,
+    /// the response between `response.created` and `response.done`
+    active_response: std::sync::Mutex<Option<String>>,
+    output_audio: std::sync::Mutex<Option<OutputAudio>>,
+    playback: Arc<std::sync::Mutex<PlaybackTracker>>,
+    output_padding: OutputPadding,
+    /// silence sent with `OutputPadding::UntilNextResponse`
+    padding: std::sync::Mutex<Option<JoinHandle<()>>>,
     tx_msg_out: UnboundedSender<Utf8Bytes>,
+    tx_function_calls: Mutex<Option<UnboundedSender<Vec<FunctionCall>>>>,
+    reconnect: ReconnectPolicy,
+    /// updated by the `WebsocketHandle` as soon as the connection drops
+    connection_state: Arc<watch::Sender<ConnectionState>>,
+    /// accumulated session configuration, replayed after reconnecting
+    session_updates: Arc<std::sync::Mutex<SessionUpdates>>,
+    /// last transcription session configuration, replayed after reconnecting
+    transcription_config: std::sync::Mutex<Option<TranscriptionSessionUpdateEvent>>,
+    /// items created by the client, replayed after reconnecting if enabled
+    history: std::sync::Mutex<Vec<(Option<String>, Item)>>,
+    pending: PendingEvents,
+    tx_events: broadcast::Sender<Event>,
+    conversation: Arc<std::sync::Mutex<Conversation>>,
+    /// tokens used by all responses so far
+    usage: std::sync::Mutex<Usage>,
+    rate_limits: std::sync::Mutex<RateLimits>,
+    rate_limit_threshold: f64,
 }
 
+/// Events buffered per subscriber before it lags behind
+const EVENT_CAPACITY: usize = 1024;
+
 impl RealtimeSession {
-    pub fn new(
+    #[allow(clippy::too_many_arguments)]
+    fn new(
         id: String,
+        model: Model,
         ws: Arc<ezsockets::Client<WebsocketHandle>>,
-    ) -> (Arc<Self>, UnboundedReceiver<Vec<u8>>) {
+        reconnect: ReconnectPolicy,
+        barge_in: bool,
+        output_padding: OutputPadding,
+        rate_limit_threshold: f64,
+        pending: PendingEvents,
+        conversation: Arc<std::sync::Mutex<Conversation>>,
+        connection_state: Arc<watch::Sender<ConnectionState>>,
+        session_updates: Arc<std::sync::Mutex<SessionUpdates>>,
+    ) -> (Arc<Self>, AudioReceiver) {
         let (tx_audio_out, rx_audio_out) = unbounded_channel();
+        let audio_generation = Arc::new(AtomicU64::new(0));
+        let delivery_format = Arc::new(std::sync::Mutex::new(None));
 
         let (tx_msg_out, mut rx_msg_out) = unbounded_channel::<Utf8Bytes>();
 
         let ws_2 = ws.clone();
+        let id_2 = id.clone();
         tokio::spawn(async move {
             while let Some(data) = rx_msg_out.recv().await {
                 match ws_2.text(data) {
@@ -205,60 +789,511 @@ impl RealtimeSession {
                     }
                 }
             }
-            panic!("websocket closed");
+            debug!("session({})> sender closed", id_2);
         });
 
         let session = Arc::new(Self {
             id,
+            model,
+            ws,
             session: Mutex::new(None),
             tx_audio: tx_audio_out,
+            audio_generation: audio_generation.clone(),
+            delivery_format: delivery_format.clone(),
+            input_format: std::sync::Mutex::new(AudioFormat::default()),
+            input_transcoder: std::sync::Mutex::new(None),
+            barge_in,
+            active_response: std::sync::Mutex::new(None),
+            output_audio: std::sync::Mutex::new(None),
+            playback: Arc::new(std::sync::Mutex::new(PlaybackTracker::default())),
+            output_padding,
+            padding: std::sync::Mutex::new(None),
             tx_msg_out: tx_msg_out.clone(),
+            tx_function_calls: Mutex::new(None),
+            reconnect,
+            connection_state,
+            session_updates,
+            transcription_config: std::sync::Mutex::new(None),
+            history: std::sync::Mutex::new(vec![]),
+            pending,
+            tx_events: broadcast::Sender::new(EVENT_CAPACITY),
+            conversation,
+            usage: std::sync::Mutex::new(Usage::default()),
+            rate_limits: std::sync::Mutex::new(RateLimits::default()),
+            rate_limit_threshold,
         });
 
-        // TODO: send from websocket to tx_audio
-
-        (session, rx_audio_out)
+        (
+            session,
+            AudioReceiver {
+                rx: rx_audio_out,
+                generation: audio_generation,
+                format: delivery_format,
+                transcoder: None,
+            },
+        )
     }
 
-    fn send(&self, evt: &str, body: impl Serialize) -> anyhow::Result<()> {
-        let body_str = serde_json::to_string_pretty(&EventMessage::wrap(evt, body))?;
-        if evt != "input_audio_buffer.append" {
-            debug!("session({})> send: {} {}", self.id, evt, body_str);
+    /// Sends a client event to the server. The returned handle reports if the server rejects it.
+    /// Fails with [`RealtimeError::ConnectionLost`] while reconnecting.
+    pub fn send(&self, evt: ClientEvent) -> Result<EventHandle, RealtimeError> {
+        let msg = EventMessage::new(evt);
+        let body_str = serde_json::to_string(&msg).map_err(RealtimeError::Serialization)?;
+        if !matches!(msg.event, ClientEvent::InputAudioBufferAppend { .. }) {
+            debug!("session({})> send: {}", self.id, body_str);
         }
-        self.tx_msg_out.send(Utf8Bytes::from(body_str))?;
-        Ok(())
+
+        // register before sending, so the error can't arrive first
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            // checked under the lock, a dropped connection fails either the send or the handle
+            match *self.connection_state.borrow() {
+                ConnectionState::Connected => {}
+                ConnectionState::Reconnecting { .. } => return Err(RealtimeError::ConnectionLost),
+                ConnectionState::Disconnected => return Err(RealtimeError::ConnectionClosed),
+            }
+            pending.retain(|_, tx| !tx.is_closed());
+            pending.insert(msg.event_id.clone(), tx);
+        }
+        self.tx_msg_out
+            .send(Utf8Bytes::from(body_str))
+            .map_err(|_| RealtimeError::ConnectionClosed)?;
+        Ok(EventHandle {
+            event_id: msg.event_id,
+            rx,
+        })
     }
 
     /// Updates the session
     /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/session/update
-    pub fn session_update(&self, session: SessionUpdateEvent) -> anyhow::Result<()> {
-        self.send(
-            "session.update",
-            json!({
-                "session": session
-            }),
-        )
+    pub fn session_update(
+        &self,
+        session: SessionUpdateEvent,
+    ) -> Result<EventHandle, RealtimeError> {
+        // held while sending, so the answer can't be handled first
+        let mut updates = self.session_updates.lock().unwrap();
+        let handle = self.send(ClientEvent::SessionUpdate {
+            session: session.clone(),
+        })?;
+        updates
+            .unconfirmed
+            .push_back((handle.event_id.clone(), session));
+        Ok(handle)
+    }
+
+    /// Updates a transcription session
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/transcription_session/update
+    pub fn transcription_session_update(
+        &self,
+        session: TranscriptionSessionUpdateEvent,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.transcription_config
+            .lock()
+            .unwrap()
+            .replace(session.clone());
+        self.send(ClientEvent::TranscriptionSessionUpdate { session })
     }
 
     /// This event instructs the server to create a Response, which means triggering model inference. When in Server VAD mode, the server will create Responses automatically.
     /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/response/create
-    pub fn response_create(&self, response: ResponseCreateEvent) -> anyhow::Result<()> {
-        self.send(
-            "response.create",
-            json!({
-                "response": response
-            }),
-        )
+    pub fn response_create(
+        &self,
+        response: ResponseCreateEvent,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::ResponseCreate { response })
+    }
+
+    /// Cancels an in-progress response. Without a `response_id` the default conversation's response is cancelled.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/response/cancel
+    pub fn response_cancel(
+        &self,
+        response_id: Option<String>,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::ResponseCancel { response_id })
     }
 
-    pub fn audio_append(&self, buffer: Vec<u8>) -> anyhow::Result<()> {
+    /// Appends audio bytes, encoded in the session's `input_audio_format`, to the input audio buffer.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/input_audio_buffer/append
+    pub fn audio_append(&self, buffer: Vec<u8>) -> Result<EventHandle, RealtimeError> {
         debug!("session({})> audio --> {} bytes", self.id, buffer.len());
-        self.send(
-            "input_audio_buffer.append",
-            json!({
-                "audio": base64::encode(buffer)
+        self.send(ClientEvent::InputAudioBufferAppend {
+            audio: BASE64_STANDARD.encode(buffer),
+        })
+    }
+
+    /// Appends audio encoded in `format`, transcoding it to the session's `input_audio_format`.
+    /// A few milliseconds are held back by the resampler until the next chunk or the commit.
+    pub fn audio_append_from(
+        &self,
+        format: AudioFormat,
+        buffer: Vec<u8>,
+    ) -> Result<EventHandle, RealtimeError> {
+        let input_format = *self.input_format.lock().unwrap();
+        if format == input_format {
+            return self.audio_append(buffer);
+        }
+        let audio = {
+            let mut transcoder = self.input_transcoder.lock().unwrap();
+            match transcoder.as_mut() {
+                Some(t) if t.from_format() == format && t.to_format() == input_format => t,
+                _ => transcoder.insert(Transcoder::new(format, input_format)),
+            }
+            .process(&buffer)
+        };
+        self.audio_append(audio)
+    }
+
+    /// The session's `input_audio_format`, as last reported by the server
+    pub fn input_audio_format(&self) -> AudioFormat {
+        *self.input_format.lock().unwrap()
+    }
+
+    /// The session's `output_audio_format`, as last reported by the server
+    pub fn output_audio_format(&self) -> AudioFormat {
+        self.playback.lock().unwrap().format()
+    }
+
+    /// Commits the input audio buffer, creating a new user message item. Not needed in Server VAD mode.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/input_audio_buffer/commit
+    pub fn input_audio_buffer_commit(&self) -> Result<EventHandle, RealtimeError> {
+        let held_back = self
+            .input_transcoder
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(Transcoder::flush)
+            .unwrap_or_default();
+        if !held_back.is_empty() {
+            self.audio_append(held_back)?;
+        }
+        self.send(ClientEvent::InputAudioBufferCommit)
+    }
+
+    /// Clears the input audio buffer.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/input_audio_buffer/clear
+    pub fn input_audio_buffer_clear(&self) -> Result<EventHandle, RealtimeError> {
+        self.input_transcoder.lock().unwrap().take();
+        self.send(ClientEvent::InputAudioBufferClear)
+    }
+
+    /// Adds an item to the conversation, after `previous_item_id` or at the end if omitted.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/conversation/item/create
+    pub fn conversation_item_create(
+        &self,
+        mut item: Item,
+        previous_item_id: Option<String>,
+    ) -> Result<EventHandle, RealtimeError> {
+        if self.reconnect.restore_history {
+            // the id tracks the item in the history, e.g. when it is deleted
+            if item.id().is_none() {
+                item.set_id(format!("item_{}", nanoid!()));
+            }
+            self.history
+                .lock()
+                .unwrap()
+                .push((previous_item_id.clone(), item.clone()));
+        }
+        self.send(ClientEvent::ConversationItemCreate {
+            previous_item_id,
+            item,
+        })
+    }
+
+    /// Asks the server for its representation of an item.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/conversation/item/retrieve
+    pub fn conversation_item_retrieve(
+        &self,
+        item_id: impl Into<String>,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::ConversationItemRetrieve {
+            item_id: item_id.into(),
+        })
+    }
+
+    /// Truncates a previous assistant message's audio at `audio_end_ms`.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/conversation/item/truncate
+    pub fn conversation_item_truncate(
+        &self,
+        item_id: impl Into<String>,
+        content_index: u32,
+        audio_end_ms: u32,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::ConversationItemTruncate {
+            item_id: item_id.into(),
+            content_index,
+            audio_end_ms,
+        })
+    }
+
+    /// Removes an item from the conversation history.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/conversation/item/delete
+    pub fn conversation_item_delete(
+        &self,
+        item_id: impl Into<String>,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::ConversationItemDelete {
+            item_id: item_id.into(),
+        })
+    }
+
+    /// Returns the result of a function call to the model and asks it to respond.
+    /// When the model requested several calls in parallel, send the outputs with
+    /// `conversation_item_create` and trigger a single `response_create` instead.
+    /// The returned handle tracks the `response.create`.
+    /// See: https://platform.openai.com/docs/guides/realtime-conversations#function-calling
+    pub fn function_call_output(
+        &self,
+        call_id: impl Into<String>,
+        output: impl Into<String>,
+    ) -> Result<EventHandle, RealtimeError> {
+        self.conversation_item_create(
+            Item::FunctionCallOutput(FunctionCallOutputItem {
+                id: None,
+                object: None,
+                status: None,
+                call_id: call_id.into(),
+                output: output.into(),
             }),
-        )
+            None,
+        )?;
+        self.response_create(ResponseCreateEvent::default())
+    }
+
+    /// WebRTC only: cuts off the current audio response.
+    /// See: https://platform.openai.com/docs/api-reference/realtime-client-events/output_audio_buffer/clear
+    pub fn output_audio_buffer_clear(&self) -> Result<EventHandle, RealtimeError> {
+        self.send(ClientEvent::OutputAudioBufferClear)
+    }
+
+    /// Returns a receiver for the function calls of each completed response.
+    /// Only one receiver is served at a time, taking another one fails until it is dropped.
+    pub async fn function_calls(
+        &self,
+    ) -> Result<UnboundedReceiver<Vec<FunctionCall>>, RealtimeError> {
+        let mut tx_function_calls = self.tx_function_calls.lock().await;
+        if tx_function_calls.as_ref().is_some_and(|tx| !tx.is_closed()) {
+            return Err(RealtimeError::InvalidState(
+                "function calls are already taken".to_string(),
+            ));
+        }
+        let (tx, rx) = unbounded_channel();
+        tx_function_calls.replace(tx);
+        Ok(rx)
+    }
+
+    /// Subscribes to all events of the session, including audio, transcripts, function calls,
+    /// connection changes and errors. Every subscriber receives every event sent after it
+    /// subscribed; one that falls more than 1024 events behind gets `RecvError::Lagged`.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx_events.subscribe()
+    }
+
+    /// Reports how much of an assistant audio content part has actually been played, so an
+    /// interruption truncates the item where the user stopped hearing it. Without a report the
+    /// item is truncated after all audio received so far.
+    pub fn set_playback_position(&self, item_id: &str, content_index: u32, played_ms: u32) {
+        if let Some(output) = self.output_audio.lock().unwrap().as_mut()
+            && output.item_id == item_id
+            && output.content_index == content_index
+        {
+            output.played_ms = Some(played_ms);
+        }
+    }
+
+    /// Acknowledges `samples` samples of the [`AudioReceiver`]'s audio as played by the sink.
+    /// Keeps the [`playback_position`](Self::playback_position) up to date, which is used to
+    /// truncate the audio on interruption.
+    pub fn audio_played(&self, samples: usize) {
+        let delivery_format = *self.delivery_format.lock().unwrap();
+        let position = {
+            let mut playback = self.playback.lock().unwrap();
+            let samples = match delivery_format {
+                Some(format) => {
+                    samples as u64 * playback.format().sample_rate() as u64
+                        / format.sample_rate() as u64
+                }
+                None => samples as u64,
+            };
+            playback.played(samples);
+            playback.position()
+        };
+        if let Some(position) = position {
+            self.set_playback_position(
+                &position.item_id,
+                position.content_index,
+                position.offset_ms,
+            );
+        }
+    }
+
+    /// The assistant audio content part played last and how much of it was played, as
+    /// acknowledged with [`audio_played`](Self::audio_played)
+    pub fn playback_position(&self) -> Option<PlaybackPosition> {
+        self.playback.lock().unwrap().position()
+    }
+
+    /// Interrupts the assistant: cancels the response in flight, drops the audio queued in the
+    /// [`AudioReceiver`] and truncates the audio item at the playback position, so the model only
+    /// remembers what the user heard. Done automatically on speech start if `barge_in` is set.
+    /// See: https://platform.openai.com/docs/guides/realtime-conversations#truncating-audio
+    pub fn interrupt(&self) -> Result<(), RealtimeError> {
+        if self.active_response.lock().unwrap().take().is_some() {
+            self.response_cancel(None)?;
+        }
+        self.audio_generation.fetch_add(1, Ordering::AcqRel);
+        let format = {
+            let mut playback = self.playback.lock().unwrap();
+            playback.flush();
+            playback.format()
+        };
+
+        let truncate = match self.output_audio.lock().unwrap().as_mut() {
+            Some(output) if !output.interrupted => {
+                output.interrupted = true;
+                let received_ms = output.received_ms(format);
+                let audio_end_ms = output.played_ms.unwrap_or(received_ms).min(received_ms);
+                (!output.done || audio_end_ms < received_ms)
+                    .then(|| (output.item_id.clone(), output.content_index, audio_end_ms))
+            }
+            _ => None,
+        };
+        if let Some((item_id, content_index, audio_end_ms)) = truncate {
+            info!(
+                "session({})> interrupted {} at {}ms",
+                self.id, item_id, audio_end_ms
+            );
+            self.conversation_item_truncate(item_id.clone(), content_index, audio_end_ms)?;
+            let _ = self.tx_events.send(Event::Interrupted {
+                item_id,
+                content_index,
+                audio_end_ms,
+            });
+        }
+        Ok(())
+    }
+
+    /// Tokens used by all responses of the session so far, as reported with `response.done`.
+    /// Survives reconnects.
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// The rate limits last reported by the server
+    pub fn rate_limits(&self) -> RateLimits {
+        self.rate_limits.lock().unwrap().clone()
+    }
+
+    /// Cost of [`usage`](Self::usage) in USD, `None` if `prices` has no prices for the model
+    pub fn cost(&self, prices: &PriceTable) -> Option<f64> {
+        prices.cost(&self.model, &self.usage())
+    }
+
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Closes the connection for good. Subscribers receive [`Event::Disconnected`].
+    pub fn close(&self) {
+        if *self.connection_state.borrow() == ConnectionState::Disconnected {
+            return;
+        }
+        info!("session({})> closing", self.id);
+        if let Err(e) = self.ws.close(None) {
+            debug!("session({})> error closing: {}", self.id, e);
+        }
+        self.closed();
+        self.broadcast(Event::Disconnected);
+    }
+
+    /// Sends `evt` to the subscribers
+    pub(crate) fn broadcast(&self, evt: Event) {
+        // fails only without subscribers
+        let _ = self.tx_events.send(evt);
+    }
+
+    fn closed(&self) {
+        self.connection_state
+            .send_replace(ConnectionState::Disconnected);
+        self.stop_padding();
+        // resolves the outstanding handles with `None`
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// Snapshot of the conversation as reported by the server
+    pub fn conversation(&self) -> Conversation {
+        self.conversation.lock().unwrap().clone()
+    }
+
+    /// Watches the state of the connection, e.g. to show "reconnecting…"
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.connection_state.subscribe()
+    }
+
+    /// Restores the client-side state on the new server-side session
+    fn replay(&self) -> Result<(), RealtimeError> {
+        let session_config = self.session_updates.lock().unwrap().confirmed.clone();
+        if let Some(session) = session_config {
+            self.session_update(session)?;
+        }
+        let transcription_config = self.transcription_config.lock().unwrap().clone();
+        if let Some(session) = transcription_config {
+            self.send(ClientEvent::TranscriptionSessionUpdate { session })?;
+        }
+        let history = self.history.lock().unwrap().clone();
+        for (previous_item_id, item) in history {
+            self.send(ClientEvent::ConversationItemCreate {
+                previous_item_id,
+                item,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Drops a deleted item from the history, items created after it take its place
+    fn forget_item(&self, item_id: &str) {
+        let mut history = self.history.lock().unwrap();
+        let Some(index) = history
+            .iter()
+            .position(|(_, item)| item.id() == Some(item_id))
+        else {
+            return;
+        };
+        let (previous_item_id, _) = history.remove(index);
+        for (previous, _) in history.iter_mut() {
+            if previous.as_deref() == Some(item_id) {
+                previous.clone_from(&previous_item_id);
+            }
+        }
+    }
+
+    /// Sends up to `max` of silence to the `AudioReceiver` in real time, until stopped
+    fn start_padding(&self, max: Duration) {
+        let playback = self.playback.clone();
+        let tx_audio = self.tx_audio.clone();
+        let generation = self.audio_generation.clone();
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PADDING_CHUNK);
+            let mut remaining = max;
+            while !remaining.is_zero() {
+                interval.tick().await;
+                let chunk = remaining.min(PADDING_CHUNK);
+                remaining -= chunk;
+                let generation = generation.load(Ordering::Acquire);
+                if !send_silence(&playback, &tx_audio, generation, chunk) {
+                    return;
+                }
+            }
+        });
+        if let Some(previous) = self.padding.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+    }
+
+    fn stop_padding(&self) {
+        if let Some(task) = self.padding.lock().unwrap().take() {
+            task.abort();
+        }
     }
 
     async fn handle_event(&self, evt: Event) {
@@ -270,40 +1305,1038 @@ impl RealtimeSession {
             _ => debug!("{:?}", evt),
         }
 
+        // up to date for the subscribers of `response.done`
+        if let Event::Server(ServerEvent::ResponseDone(evt)) = &evt
+            && let Some(usage) = evt.response.usage
+        {
+            *self.usage.lock().unwrap() += usage;
+        }
+
+        // fails only without subscribers
+        let _ = self.tx_events.send(evt.clone());
+
         match evt {
-            Event::Audio(audio) => match self.tx_audio.send(audio) {
-                Ok(_) => {}
-                Err(e) => {
+            Event::Audio(audio) => {
+                let part = match self.output_audio.lock().unwrap().as_mut() {
+                    Some(output) if output.interrupted => return,
+                    Some(output) if !output.done => {
+                        output.received_bytes += audio.len();
+                        Some((output.item_id.clone(), output.content_index))
+                    }
+                    _ => None,
+                };
+                let format = {
+                    let mut playback = self.playback.lock().unwrap();
+                    playback.push(
+                        part.as_ref().map(|(id, index)| (id.as_str(), *index)),
+                        audio.len(),
+                    );
+                    playback.format()
+                };
+                let generation = self.audio_generation.load(Ordering::Acquire);
+                if let Err(e) = self.tx_audio.send((generation, format, audio)) {
                     error!("error handling audio event: {}", e);
                 }
-            },
+            }
+            Event::AudioStarted {
+                item_id,
+                content_index,
+                ..
+            } => {
+                self.stop_padding();
+                self.output_audio.lock().unwrap().replace(OutputAudio {
+                    item_id,
+                    content_index,
+                    received_bytes: 0,
+                    played_ms: None,
+                    done: false,
+                    interrupted: false,
+                });
+            }
+            Event::AudioDone { .. } => {
+                if let Some(output) = self.output_audio.lock().unwrap().as_mut() {
+                    output.done = true;
+                }
+                // ends the part, receivers flush the audio held back by their resamplers
+                let format = self.playback.lock().unwrap().format();
+                let generation = self.audio_generation.load(Ordering::Acquire);
+                let _ = self.tx_audio.send((generation, format, vec![]));
+                match self.output_padding {
+                    OutputPadding::None => {}
+                    OutputPadding::Fixed(duration) => {
+                        let generation = self.audio_generation.load(Ordering::Acquire);
+                        send_silence(&self.playback, &self.tx_audio, generation, duration);
+                    }
+                    OutputPadding::UntilNextResponse { max } => self.start_padding(max),
+                }
+            }
             Event::SessionCreated(session) => {
                 info!("Session created: {}", session.id);
+                self.playback
+                    .lock()
+                    .unwrap()
+                    .set_format(session.output_audio_format);
+                *self.input_format.lock().unwrap() = session.input_audio_format;
                 {
                     self.session.lock().await.replace(session);
                 }
             }
+            Event::Server(ServerEvent::SessionUpdated(evt)) => {
+                self.playback
+                    .lock()
+                    .unwrap()
+                    .set_format(evt.session.output_audio_format);
+                *self.input_format.lock().unwrap() = evt.session.input_audio_format;
+                self.session.lock().await.replace(evt.session);
+            }
+            Event::Server(
+                ServerEvent::TranscriptionSessionCreated(evt)
+                | ServerEvent::TranscriptionSessionUpdated(evt),
+            ) => {
+                *self.input_format.lock().unwrap() = evt.session.input_audio_format;
+            }
             Event::TranscriptDone(transcript) => {
                 info!("transcript done: {transcript}");
             }
-            Event::InputAudioBufferSpeechStarted => {
-                //println!("STARTED");
-                //rt_client_events.send("response.cancel", json!({}))
-                //rt_client_events.send("response.cancel", json!({}))
+            Event::InputTranscriptDone {
+                item_id,
+                transcript,
+                ..
+            } => {
+                info!("input transcript done ({item_id}): {transcript}");
+            }
+            Event::InputTranscriptFailed { item_id, error, .. } => {
+                error!(
+                    "session({})> transcription of {} failed: {}",
+                    self.id, item_id, error.message
+                );
+            }
+            Event::InputAudioBufferSpeechStarted { .. } => {
+                if self.barge_in
+                    && let Err(e) = self.interrupt()
+                {
+                    error!("session({})> error interrupting: {}", self.id, e);
+                }
+            }
+            Event::Server(ServerEvent::ResponseCreated(evt)) => {
+                self.stop_padding();
+                self.active_response
+                    .lock()
+                    .unwrap()
+                    .replace(evt.response.id);
+            }
+            Event::Reconnected => {
+                if let Err(e) = self.replay() {
+                    error!("error restoring session: {}", e);
+                }
+            }
+            Event::Disconnected => self.closed(),
+            Event::Server(ServerEvent::ConversationItemDeleted(evt)) => {
+                self.forget_item(&evt.item_id);
+            }
+            Event::Server(ServerEvent::RateLimitsUpdated(evt)) => {
+                let crossed = self
+                    .rate_limits
+                    .lock()
+                    .unwrap()
+                    .update(evt.rate_limits, self.rate_limit_threshold);
+                for limit in crossed {
+                    info!(
+                        "session({})> rate limit {:?} low: {} of {} remaining",
+                        self.id, limit.name, limit.remaining, limit.limit
+                    );
+                    self.broadcast(Event::RateLimitLow(limit));
+                }
+            }
+            Event::Error(e) => {
+                error!("session({})> {}", self.id, e);
+            }
+            Event::Server(ServerEvent::ResponseDone(evt)) => {
+                self.active_response.lock().unwrap().take();
+                if evt.response.status != ResponseStatus::Completed {
+                    return;
+                }
+                let calls: Vec<FunctionCall> = evt
+                    .response
+                    .output
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        Item::FunctionCall(call) => Some(FunctionCall {
+                            response_id: evt.response.id.clone(),
+                            item_id: call.id.unwrap_or_default(),
+                            call_id: call.call_id,
+                            name: call.name,
+                            arguments: call.arguments,
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+                if calls.is_empty() {
+                    return;
+                }
+                if let Some(tx) = self.tx_function_calls.lock().await.as_ref()
+                    && let Err(e) = tx.send(calls)
+                {
+                    error!("error handling function calls: {}", e);
+                }
             }
             _ => {}
         }
     }
 }
 
+impl Drop for RealtimeSession {
+    fn drop(&mut self) {
+        self.stop_padding();
+    }
+}
+
+/// Chunk size of `OutputPadding::UntilNextResponse`
+const PADDING_CHUNK: Duration = Duration::from_millis(20);
+
+/// Sends `duration` of silence in the session's output format to the `AudioReceiver`. Returns
+/// `false` once the receiver is gone.
+fn send_silence(
+    playback: &std::sync::Mutex<PlaybackTracker>,
+    tx_audio: &UnboundedSender<(u64, AudioFormat, Vec<u8>)>,
+    generation: u64,
+    duration: Duration,
+) -> bool {
+    let (format, silence) = {
+        let mut playback = playback.lock().unwrap();
+        let silence = codec::silence(playback.format(), duration);
+        playback.push(None, silence.len());
+        (playback.format(), silence)
+    };
+    tx_audio.send((generation, format, silence)).is_ok()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::WebsocketConfig;
-    use crate::websocket::connect;
+    use crate::api::client_event::ClientEvent;
+    use crate::api::item::{ContentPart, Item, MessageItem, Role};
+    use crate::api::server_event::{
+        RateLimitName, ResponseAudioDoneEvent, ResponseDeltaEvent, ServerEvent,
+    };
+    use crate::api::session::{
+        AudioFormat, InputAudioTranscription, NoiseReduction, SessionUpdateEvent, TurnDetection,
+    };
+    use crate::config::{ApiKeyRef, TokenProvider};
+    use crate::error::RealtimeError;
+    use crate::event::Event;
+    use crate::testing::{MOCK_API_KEY, MockServer};
+    use crate::websocket::config::{OutputPadding, ReconnectPolicy, WebsocketConfig};
+    use crate::websocket::{ConnectionState, connect};
+    use base64::prelude::*;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn it_works() {
-        let client = connect(WebsocketConfig::default()).await.unwrap();
-        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config
+            .endpoint
+            .headers
+            .push(("x-proxy-token".to_string(), "secret".to_string()));
+        config
+            .endpoint
+            .query
+            .push(("tenant".to_string(), "acme".to_string()));
+        let (session, mut rx_audio) = connect(config).await.unwrap();
+
+        let handshake = &mock.requests()[0];
+        assert_eq!(handshake.path, "/v1/realtime");
+        assert_eq!(
+            handshake.headers.get("authorization").unwrap(),
+            "Bearer test-key"
+        );
+        assert_eq!(handshake.headers.get("x-proxy-token").unwrap(), "secret");
+        assert_eq!(handshake.query.get("tenant").unwrap(), "acme");
+        assert!(handshake.query.contains_key("model"));
+
+        session
+            .session_update(SessionUpdateEvent {
+                instructions: Some("be brief".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::SessionUpdate { .. }))
+            .await
+            .unwrap();
+
+        mock.send(ServerEvent::ResponseAudioDelta(ResponseDeltaEvent {
+            event_id: "event_1".to_string(),
+            response_id: "resp_1".to_string(),
+            item_id: "item_1".to_string(),
+            output_index: 0,
+            content_index: 0,
+            delta: BASE64_STANDARD.encode([1, 2, 3, 4]),
+        }));
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_event() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+
+        let accepted = session
+            .session_update(SessionUpdateEvent {
+                temperature: Some(0.8),
+                ..Default::default()
+            })
+            .unwrap();
+        let rejected = session
+            .session_update(SessionUpdateEvent {
+                temperature: Some(2.0),
+                ..Default::default()
+            })
+            .unwrap();
+        let event_id = rejected.event_id().to_string();
+
+        let Err(RealtimeError::Server(details)) = rejected.check(Duration::from_secs(5)).await
+        else {
+            panic!("expected the session.update to be rejected")
+        };
+        assert_eq!(details.event_id, Some(event_id));
+        assert_eq!(details.param.as_deref(), Some("session.temperature"));
+        assert!(accepted.check(Duration::from_millis(50)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut ui = session.subscribe();
+        let mut logger = session.subscribe();
+
+        mock.send(ServerEvent::ResponseAudioTranscriptDelta(
+            ResponseDeltaEvent {
+                event_id: "event_1".to_string(),
+                response_id: "resp_1".to_string(),
+                item_id: "item_1".to_string(),
+                output_index: 0,
+                content_index: 0,
+                delta: "Hello".to_string(),
+            },
+        ));
+        mock.send_raw("not json");
+
+        for events in [&mut ui, &mut logger] {
+            let mut next = async || loop {
+                match events.recv().await.unwrap() {
+                    Event::TranscriptDelta(delta) => break Some(delta),
+                    Event::Error(err) => {
+                        assert!(matches!(*err, RealtimeError::Decode { .. }), "{err}");
+                        break None;
+                    }
+                    _ => continue,
+                }
+            };
+            assert_eq!(next().await.as_deref(), Some("Hello"));
+            assert_eq!(next().await, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_barge_in() {
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config.barge_in = true;
+        let (session, mut rx_audio) = connect(config).await.unwrap();
+        let audio_part = |response_id: &str, item_id: &str| {
+            json!({
+                "type": "response.content_part.added", "event_id": "event_1",
+                "response_id": response_id, "item_id": item_id, "output_index": 0,
+                "content_index": 0, "part": { "type": "audio", "transcript": "" }
+            })
+            .to_string()
+        };
+        let audio_delta = |response_id: &str, item_id: &str, audio: &[u8]| {
+            json!({
+                "type": "response.audio.delta", "event_id": "event_2",
+                "response_id": response_id, "item_id": item_id, "output_index": 0,
+                "content_index": 0, "delta": BASE64_STANDARD.encode(audio)
+            })
+            .to_string()
+        };
+
+        mock.send_raw(
+            json!({
+                "type": "response.created", "event_id": "event_0",
+                "response": { "id": "resp_1", "object": "realtime.response", "status": "in_progress", "output": [] }
+            })
+            .to_string(),
+        );
+        mock.send_raw(audio_part("resp_1", "item_1"));
+        // 100ms of audio
+        mock.send_raw(audio_delta("resp_1", "item_1", &[1; 4800]));
+        assert_eq!(rx_audio.recv().await.unwrap().len(), 4800);
+        mock.send_raw(audio_delta("resp_1", "item_1", &[2; 4800]));
+        // 40ms played
+        session.audio_played(960);
+        assert_eq!(session.playback_position().unwrap().offset_ms, 40);
+
+        mock.send_raw(
+            json!({
+                "type": "input_audio_buffer.speech_started", "event_id": "event_3",
+                "audio_start_ms": 1000, "item_id": "item_0"
+            })
+            .to_string(),
+        );
+        mock.wait_for(|evt| matches!(evt, ClientEvent::ResponseCancel { response_id: None }))
+            .await
+            .unwrap();
+        let (_, truncate) = mock
+            .wait_for(|evt| matches!(evt, ClientEvent::ConversationItemTruncate { .. }))
+            .await
+            .unwrap();
+        assert!(matches!(
+            truncate,
+            ClientEvent::ConversationItemTruncate { item_id, content_index: 0, audio_end_ms: 40 }
+                if item_id == "item_1"
+        ));
+
+        // queued and late audio of the interrupted item is dropped
+        mock.send_raw(audio_delta("resp_1", "item_1", &[3; 4800]));
+        mock.send_raw(audio_part("resp_2", "item_2"));
+        mock.send_raw(audio_delta("resp_2", "item_2", &[9, 9]));
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![9, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_output_padding() {
+        let mock = MockServer::start().await;
+        let audio_events = |item_id: &str, audio: &[u8]| {
+            [
+                json!({
+                    "type": "response.content_part.added", "event_id": "event_1",
+                    "response_id": "resp_1", "item_id": item_id, "output_index": 0,
+                    "content_index": 0, "part": { "type": "audio", "transcript": "" }
+                }),
+                json!({
+                    "type": "response.audio.delta", "event_id": "event_2",
+                    "response_id": "resp_1", "item_id": item_id, "output_index": 0,
+                    "content_index": 0, "delta": BASE64_STANDARD.encode(audio)
+                }),
+                json!({
+                    "type": "response.audio.done", "event_id": "event_3",
+                    "response_id": "resp_1", "item_id": item_id, "output_index": 0,
+                    "content_index": 0
+                }),
+            ]
+        };
+
+        // no padding by default, the boundaries are events
+        let (session, mut rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        for evt in audio_events("item_1", &[1, 1]) {
+            mock.send_raw(evt.to_string());
+        }
+        let started = loop {
+            if let Event::AudioStarted { item_id, .. } = events.recv().await.unwrap() {
+                break item_id;
+            }
+        };
+        assert_eq!(started, "item_1");
+        while !matches!(events.recv().await.unwrap(), Event::AudioDone { .. }) {}
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![1, 1]);
+        for evt in audio_events("item_2", &[2, 2]) {
+            mock.send_raw(evt.to_string());
+        }
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![2, 2]);
+
+        // 50ms of PCM16 silence
+        let (_session, mut rx_audio) = connect(WebsocketConfig {
+            output_padding: OutputPadding::Fixed(Duration::from_millis(50)),
+            ..mock.websocket_config()
+        })
+        .await
+        .unwrap();
+        for evt in audio_events("item_1", &[1, 1]) {
+            mock.send_raw(evt.to_string());
+        }
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![1, 1]);
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![0; 2400]);
+
+        // silence until the next audio part starts
+        let (session, mut rx_audio) = connect(WebsocketConfig {
+            output_padding: OutputPadding::UntilNextResponse {
+                max: Duration::from_secs(5),
+            },
+            ..mock.websocket_config()
+        })
+        .await
+        .unwrap();
+        let mut events = session.subscribe();
+        for evt in audio_events("item_1", &[1, 1]) {
+            mock.send_raw(evt.to_string());
+        }
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![1, 1]);
+        for _ in 0..3 {
+            assert_eq!(rx_audio.recv().await.unwrap(), vec![0; 960]);
+        }
+        for evt in audio_events("item_2", &[2, 2]) {
+            mock.send_raw(evt.to_string());
+        }
+        while rx_audio.recv().await.unwrap() != vec![2, 2] {}
+        // the padding is played, but doesn't count for the item
+        session.audio_played(1 + 480);
+        assert_eq!(session.playback_position().unwrap().item_id, "item_1");
+
+        // or until the next response starts, e.g. one without audio
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![0; 960]);
+        mock.send_raw(
+            json!({
+                "type": "response.created", "event_id": "event_4",
+                "response": { "id": "resp_2", "object": "realtime.response", "status": "in_progress", "output": [] }
+            })
+            .to_string(),
+        );
+        while !matches!(
+            events.recv().await.unwrap(),
+            Event::Server(ServerEvent::ResponseCreated(_))
+        ) {}
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        while rx_audio.try_recv().is_ok() {}
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(rx_audio.try_recv().is_err());
+
+        // and never longer than `max`
+        let (_session, mut rx_audio) = connect(WebsocketConfig {
+            output_padding: OutputPadding::UntilNextResponse {
+                max: Duration::from_millis(50),
+            },
+            ..mock.websocket_config()
+        })
+        .await
+        .unwrap();
+        for evt in audio_events("item_1", &[1, 1]) {
+            mock.send_raw(evt.to_string());
+        }
+        assert_eq!(rx_audio.recv().await.unwrap(), vec![1, 1]);
+        for len in [960, 960, 480] {
+            assert_eq!(rx_audio.recv().await.unwrap(), vec![0; len]);
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(rx_audio.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_usage() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        let response = |event: &str, id: &str, status: &str| {
+            json!({
+                "type": event, "event_id": "event_1",
+                "response": {
+                    "id": id, "object": "realtime.response", "status": status, "output": [],
+                    "usage": {
+                        "total_tokens": 30, "input_tokens": 20, "output_tokens": 10,
+                        "input_token_details": { "text_tokens": 5, "audio_tokens": 15, "cached_tokens": 0 },
+                        "output_token_details": { "text_tokens": 2, "audio_tokens": 8 }
+                    }
+                }
+            })
+            .to_string()
+        };
+        mock.send_raw(response("response.done", "resp_1", "completed"));
+        mock.send_raw(
+            json!({
+                "type": "response.done", "event_id": "event_2",
+                "response": {
+                    "id": "resp_2", "object": "realtime.response", "status": "cancelled",
+                    "status_details": { "type": "cancelled", "reason": "turn_detected" },
+                    "output": [],
+                    "usage": {
+                        "total_tokens": 3, "input_tokens": 2, "output_tokens": 1,
+                        "input_token_details": { "text_tokens": 2, "audio_tokens": 0, "cached_tokens": 0 },
+                        "output_token_details": { "text_tokens": 1, "audio_tokens": 0 }
+                    }
+                }
+            })
+            .to_string(),
+        );
+        // events are handled in order, after being broadcast
+        mock.send_raw(response("response.created", "resp_3", "in_progress"));
+        while !matches!(
+            events.recv().await.unwrap(),
+            Event::Server(ServerEvent::ResponseCreated(_))
+        ) {}
+
+        let usage = session.usage();
+        assert_eq!(usage.total_tokens, 33);
+        assert_eq!(usage.input_token_details.audio_tokens, 15);
+        assert_eq!(usage.output_token_details.text_tokens, 3);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        let rate_limits = |remaining: u64| {
+            json!({
+                "type": "rate_limits.updated", "event_id": "event_1",
+                "rate_limits": [
+                    { "name": "requests", "limit": 1000, "remaining": 999, "reset_seconds": 60 },
+                    { "name": "tokens", "limit": 50000, "remaining": remaining, "reset_seconds": 1.2 }
+                ]
+            })
+            .to_string()
+        };
+        mock.send_raw(rate_limits(40_000));
+        mock.send_raw(rate_limits(4_000));
+        let limit = loop {
+            if let Event::RateLimitLow(limit) = events.recv().await.unwrap() {
+                break limit;
+            }
+        };
+        assert_eq!(limit.name, RateLimitName::Tokens);
+        assert_eq!(limit.remaining, 4_000);
+        assert_eq!(session.rate_limits().tokens().unwrap().remaining, 4_000);
+        assert_eq!(session.rate_limits().requests().unwrap().remaining, 999);
+    }
+
+    #[tokio::test]
+    async fn test_input_transcription() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        session
+            .session_update(SessionUpdateEvent {
+                input_audio_transcription: Some(Some(InputAudioTranscription {
+                    language: Some("de".to_string()),
+                    ..InputAudioTranscription::new("gpt-4o-transcribe")
+                })),
+                ..Default::default()
+            })
+            .unwrap();
+        let updated = loop {
+            if let Event::Server(ServerEvent::SessionUpdated(evt)) = events.recv().await.unwrap() {
+                break evt.session;
+            }
+        };
+        let transcription = updated.input_audio_transcription.unwrap();
+        assert_eq!(transcription.model.as_deref(), Some("gpt-4o-transcribe"));
+        assert_eq!(transcription.language.as_deref(), Some("de"));
+
+        for evt in [
+            json!({
+                "type": "conversation.item.input_audio_transcription.delta", "event_id": "event_1",
+                "item_id": "item_1", "content_index": 0, "delta": "Hallo"
+            }),
+            json!({
+                "type": "conversation.item.input_audio_transcription.completed", "event_id": "event_2",
+                "item_id": "item_1", "content_index": 0, "transcript": "Hallo Welt"
+            }),
+            json!({
+                "type": "conversation.item.input_audio_transcription.failed", "event_id": "event_3",
+                "item_id": "item_2", "content_index": 0,
+                "error": { "type": "transcription_error", "code": "audio_unintelligible", "message": "Audio is unintelligible" }
+            }),
+        ] {
+            mock.send_raw(evt.to_string());
+        }
+        let mut transcripts = vec![];
+        loop {
+            match events.recv().await.unwrap() {
+                Event::InputTranscriptDelta { item_id, delta, .. } => {
+                    transcripts.push(format!("{item_id}: {delta}..."))
+                }
+                Event::InputTranscriptDone {
+                    item_id,
+                    transcript,
+                    ..
+                } => transcripts.push(format!("{item_id}: {transcript}")),
+                Event::InputTranscriptFailed { item_id, error, .. } => {
+                    transcripts.push(format!("{item_id}: {}", error.code.unwrap()));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            transcripts,
+            [
+                "item_1: Hallo...",
+                "item_1: Hallo Welt",
+                "item_2: audio_unintelligible"
+            ]
+        );
+
+        // and turned off again
+        session
+            .session_update(SessionUpdateEvent {
+                input_audio_transcription: Some(None),
+                ..Default::default()
+            })
+            .unwrap();
+        let updated = loop {
+            if let Event::Server(ServerEvent::SessionUpdated(evt)) = events.recv().await.unwrap() {
+                break evt.session;
+            }
+        };
+        assert!(updated.input_audio_transcription.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_noise_reduction() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        for noise_reduction in [NoiseReduction::FarField, NoiseReduction::Disabled] {
+            session
+                .session_update(SessionUpdateEvent {
+                    input_audio_noise_reduction: Some(noise_reduction),
+                    ..Default::default()
+                })
+                .unwrap();
+            let updated = loop {
+                if let Event::Server(ServerEvent::SessionUpdated(evt)) =
+                    events.recv().await.unwrap()
+                {
+                    break evt.session;
+                }
+            };
+            assert_eq!(updated.input_audio_noise_reduction, noise_reduction);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turn_detection() {
+        let mock = MockServer::start().await;
+        let (session, _rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        for turn_detection in [TurnDetection::semantic_vad(), TurnDetection::None] {
+            session
+                .session_update(SessionUpdateEvent {
+                    turn_detection: Some(turn_detection.clone()),
+                    ..Default::default()
+                })
+                .unwrap();
+            let updated = loop {
+                if let Event::Server(ServerEvent::SessionUpdated(evt)) =
+                    events.recv().await.unwrap()
+                {
+                    break evt.session;
+                }
+            };
+            assert_eq!(updated.turn_detection, turn_detection);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_g711_session() {
+        let mock = MockServer::start().await;
+        let (session, mut rx_audio) = connect(mock.websocket_config()).await.unwrap();
+        let mut events = session.subscribe();
+        session
+            .session_update(SessionUpdateEvent {
+                input_audio_format: Some(AudioFormat::G711Ulaw),
+                output_audio_format: Some(AudioFormat::G711Ulaw),
+                ..Default::default()
+            })
+            .unwrap();
+        while !matches!(
+            events.recv().await.unwrap(),
+            Event::Server(ServerEvent::SessionUpdated(_))
+        ) {}
+
+        // 10ms of PCM16 are sent as 10ms of μ-law, the resampler's tail with the commit
+        session
+            .audio_append_from(AudioFormat::PCM16, vec![0; 480])
+            .unwrap();
+        session.input_audio_buffer_commit().unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::InputAudioBufferCommit))
+            .await
+            .unwrap();
+        let sent: Vec<u8> = mock
+            .received()
+            .into_iter()
+            .filter_map(|evt| match evt {
+                ClientEvent::InputAudioBufferAppend { audio } => {
+                    Some(BASE64_STANDARD.decode(audio).unwrap())
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(sent, vec![0xff; 80]);
+
+        // and 10ms of μ-law are delivered as PCM16, the tail once the audio is done
+        rx_audio.set_format(Some(AudioFormat::PCM16));
+        mock.send(ServerEvent::ResponseAudioDelta(ResponseDeltaEvent {
+            event_id: "event_1".to_string(),
+            response_id: "resp_1".to_string(),
+            item_id: "item_1".to_string(),
+            output_index: 0,
+            content_index: 0,
+            delta: BASE64_STANDARD.encode([0xff; 80]),
+        }));
+        mock.send(ServerEvent::ResponseAudioDone(ResponseAudioDoneEvent {
+            event_id: "event_2".to_string(),
+            response_id: "resp_1".to_string(),
+            item_id: "item_1".to_string(),
+            output_index: 0,
+            content_index: 0,
+        }));
+        let mut received = rx_audio.recv().await.unwrap();
+        assert!(received.len() < 480);
+        received.extend(rx_audio.recv().await.unwrap());
+        assert_eq!(received, vec![0; 480]);
+    }
+
+    #[tokio::test]
+    async fn test_connect_unauthorized() {
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config.api_key_ref = ApiKeyRef::Value("wrong-key".to_string());
+        let Err(err) = connect(config).await else {
+            panic!("expected connect to fail")
+        };
+        assert!(matches!(err, RealtimeError::Auth(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_token() {
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config.reconnect.initial_backoff = Duration::from_millis(10);
+        let tokens = Arc::new(AtomicU64::new(0));
+        config.api_key_ref = ApiKeyRef::Provider(TokenProvider::new({
+            let tokens = tokens.clone();
+            move || {
+                tokens.fetch_add(1, Ordering::SeqCst);
+                async { Ok(MOCK_API_KEY.to_string()) }
+            }
+        }));
+        let (session, _rx_audio) = connect(config).await.unwrap();
+        let mut state = session.connection_state();
+        assert_eq!(tokens.load(Ordering::SeqCst), 1);
+
+        // every connection attempt asks for a fresh token
+        mock.disconnect_all();
+        state
+            .wait_for(|s| matches!(s, ConnectionState::Reconnecting { .. }))
+            .await
+            .unwrap();
+        state
+            .wait_for(|s| *s == ConnectionState::Connected)
+            .await
+            .unwrap();
+        assert_eq!(tokens.load(Ordering::SeqCst), 2);
+        session.close();
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config.reconnect = ReconnectPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(100),
+            restore_history: true,
+            ..Default::default()
+        };
+        let (session, _rx_audio) = connect(config).await.unwrap();
+        let mut state = session.connection_state();
+
+        session
+            .session_update(SessionUpdateEvent {
+                instructions: Some("be brief".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        session
+            .session_update(SessionUpdateEvent {
+                temperature: Some(0.6),
+                ..Default::default()
+            })
+            .unwrap();
+        // rejected updates are not replayed
+        let rejected = session
+            .session_update(SessionUpdateEvent {
+                temperature: Some(2.0),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(rejected.await.is_some());
+        session
+            .conversation_item_create(
+                Item::Message(MessageItem {
+                    id: None,
+                    object: None,
+                    status: None,
+                    role: Role::User,
+                    content: vec![ContentPart::InputText {
+                        text: "hello".to_string(),
+                    }],
+                }),
+                None,
+            )
+            .unwrap();
+        mock.wait_for(|evt| matches!(evt, ClientEvent::ConversationItemCreate { .. }))
+            .await
+            .unwrap();
+        // never answered by the mock
+        let in_flight = session.response_cancel(None).unwrap();
+
+        mock.disconnect_all();
+        state
+            .wait_for(|s| matches!(s, ConnectionState::Reconnecting { attempt: 1 }))
+            .await
+            .unwrap();
+        assert!(matches!(
+            in_flight.await,
+            Some(RealtimeError::ConnectionLost)
+        ));
+        assert!(matches!(
+            session.input_audio_buffer_commit(),
+            Err(RealtimeError::ConnectionLost)
+        ));
+        state
+            .wait_for(|s| *s == ConnectionState::Connected)
+            .await
+            .unwrap();
+
+        // the merged configuration and the history are replayed
+        let (_, replayed) = mock
+            .wait_for(|evt| {
+                matches!(evt, ClientEvent::SessionUpdate { session }
+                    if session.instructions.is_some() && session.temperature.is_some())
+            })
+            .await
+            .unwrap();
+        let ClientEvent::SessionUpdate { session: replayed } = replayed else {
+            unreachable!()
+        };
+        assert_eq!(replayed.instructions.as_deref(), Some("be brief"));
+        assert_eq!(replayed.temperature, Some(0.6));
+        let items_created = || {
+            mock.received()
+                .iter()
+                .filter(|evt| matches!(evt, ClientEvent::ConversationItemCreate { .. }))
+                .count()
+        };
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while items_created() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        // gives up once the server is gone
+        drop(mock);
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            state.wait_for(|s| *s == ConnectionState::Disconnected),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(matches!(
+            session.input_audio_buffer_commit(),
+            Err(RealtimeError::ConnectionClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_deleted_items() {
+        let mock = MockServer::start().await;
+        let mut config = mock.websocket_config();
+        config.reconnect = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            restore_history: true,
+            ..Default::default()
+        };
+        let (session, _rx_audio) = connect(config).await.unwrap();
+        let mut events = session.subscribe();
+        let mut state = session.connection_state();
+        let message = |id: Option<&str>, text: &str| {
+            Item::Message(MessageItem {
+                id: id.map(str::to_string),
+                object: None,
+                status: None,
+                role: Role::User,
+                content: vec![ContentPart::InputText {
+                    text: text.to_string(),
+                }],
+            })
+        };
+
+        session
+            .conversation_item_create(message(Some("msg_1"), "first"), None)
+            .unwrap();
+        session
+            .conversation_item_create(message(None, "second"), Some("msg_1".to_string()))
+            .unwrap();
+        session
+            .conversation_item_create(message(Some("msg_3"), "third"), None)
+            .unwrap();
+        let (_, created) = mock
+            .wait_for(|evt| {
+                matches!(evt, ClientEvent::ConversationItemCreate { previous_item_id, .. }
+                    if previous_item_id.is_some())
+            })
+            .await
+            .unwrap();
+        let ClientEvent::ConversationItemCreate { item: second, .. } = created else {
+            unreachable!()
+        };
+        let second_id = second.id().unwrap().to_string();
+
+        // a rejected delete keeps the item
+        let rejected = session.conversation_item_delete("msg_2").unwrap().await;
+        assert!(matches!(rejected, Some(RealtimeError::Server(_))));
+        session.conversation_item_delete("msg_1").unwrap();
+        while !matches!(
+            events.recv().await.unwrap(),
+            Event::Server(ServerEvent::ConversationItemDeleted(_))
+        ) {}
+
+        mock.disconnect_all();
+        state
+            .wait_for(|s| matches!(s, ConnectionState::Reconnecting { .. }))
+            .await
+            .unwrap();
+        state
+            .wait_for(|s| *s == ConnectionState::Connected)
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while mock
+                .received()
+                .iter()
+                .filter(|evt| matches!(evt, ClientEvent::ConversationItemCreate { .. }))
+                .count()
+                < 5
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let replayed: Vec<_> = mock
+            .received()
+            .into_iter()
+            .filter_map(|evt| match evt {
+                ClientEvent::ConversationItemCreate {
+                    previous_item_id,
+                    item,
+                } => Some((previous_item_id, item.id().map(str::to_string))),
+                _ => None,
+            })
+            .skip(3)
+            .collect();
+        assert_eq!(
+            replayed,
+            [(None, Some(second_id)), (None, Some("msg_3".to_string())),]
+        );
     }
 }